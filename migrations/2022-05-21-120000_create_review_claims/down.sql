@@ -0,0 +1 @@
+DROP TABLE review_claims;
\ No newline at end of file
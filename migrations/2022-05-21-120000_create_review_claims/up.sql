@@ -0,0 +1,9 @@
+CREATE TABLE review_claims
+(
+    id         BIGINT AUTO_INCREMENT PRIMARY KEY,
+    user       BIGINT NOT NULL UNIQUE,
+    reviewer   TEXT,
+    expiration BIGINT,
+    created    BIGINT
+
+)
@@ -0,0 +1 @@
+DROP TABLE discovery_runs;
\ No newline at end of file
@@ -0,0 +1,12 @@
+CREATE TABLE discovery_runs
+(
+    id       BIGINT AUTO_INCREMENT PRIMARY KEY,
+    source   TEXT,
+    started  BIGINT,
+    finished BIGINT,
+    scanned  BIGINT,
+    matched  BIGINT,
+    inserted BIGINT,
+    errors   BIGINT
+
+)
@@ -0,0 +1 @@
+DROP TABLE titles;
\ No newline at end of file
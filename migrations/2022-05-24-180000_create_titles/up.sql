@@ -0,0 +1,19 @@
+CREATE TABLE titles
+(
+    id              BIGINT AUTO_INCREMENT PRIMARY KEY,
+    value           VARCHAR(255) NOT NULL UNIQUE,
+    proper_name     TEXT,
+    description     TEXT         DEFAULT NULL,
+    color           TEXT         DEFAULT NULL,
+    possible_titles TEXT         DEFAULT NULL,
+    priority        BIGINT       DEFAULT NULL,
+    exclude         TEXT         DEFAULT NULL,
+    position        TEXT         DEFAULT NULL,
+    word_boundary   BOOLEAN      DEFAULT NULL,
+    leetspeak       BOOLEAN      DEFAULT NULL,
+    ordering        BIGINT,
+    retired         BOOLEAN      DEFAULT FALSE,
+    created         BIGINT,
+    updated         BIGINT
+
+)
@@ -0,0 +1 @@
+ALTER TABLE users DROP COLUMN title_manual;
\ No newline at end of file
@@ -0,0 +1,8 @@
+ALTER TABLE users
+    ADD COLUMN title_manual BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- The old matcher only gave out titles found inside the username. Any other title was set by hand
+UPDATE users
+SET title_manual = TRUE
+WHERE title <> 'No Title Identified'
+  AND LOCATE(LOWER(title), LOWER(username)) = 0;
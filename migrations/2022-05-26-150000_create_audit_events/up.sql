@@ -0,0 +1,14 @@
+CREATE TABLE audit_events
+(
+    id        BIGINT AUTO_INCREMENT PRIMARY KEY,
+    actor     VARCHAR(255) NOT NULL,
+    target    VARCHAR(255) NOT NULL,
+    action    VARCHAR(64)  NOT NULL,
+    old_value TEXT,
+    new_value TEXT,
+    created   BIGINT       NOT NULL,
+    INDEX audit_events_actor (actor),
+    INDEX audit_events_target (target),
+    INDEX audit_events_created (created)
+
+)
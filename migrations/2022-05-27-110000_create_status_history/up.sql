@@ -0,0 +1,13 @@
+CREATE TABLE status_history
+(
+    id         BIGINT AUTO_INCREMENT PRIMARY KEY,
+    user       BIGINT NOT NULL,
+    old_status TEXT,
+    status     TEXT,
+    reviewer   TEXT,
+    title      TEXT,
+    note       TEXT DEFAULT NULL,
+    created    BIGINT,
+    INDEX status_history_user (user)
+
+)
@@ -0,0 +1 @@
+DROP TABLE review_decisions;
\ No newline at end of file
@@ -0,0 +1,12 @@
+CREATE TABLE review_decisions
+(
+    id       BIGINT AUTO_INCREMENT PRIMARY KEY,
+    user     BIGINT NOT NULL,
+    reviewer TEXT,
+    status   TEXT,
+    reason   TEXT DEFAULT NULL,
+    note     TEXT DEFAULT NULL,
+    created  BIGINT,
+    INDEX review_decisions_user (user)
+
+)
@@ -0,0 +1,38 @@
+ALTER TABLE users
+    ADD COLUMN permissions TEXT AFTER password_changed;
+
+UPDATE users
+SET permissions = CONCAT('{"admin":',
+                         IF(EXISTS(SELECT 1
+                                   FROM user_roles
+                                            JOIN roles ON roles.id = user_roles.role
+                                   WHERE user_roles.user = users.id
+                                     AND roles.name = 'Admin'), 'true', 'false'),
+                         ',"moderator":',
+                         IF(EXISTS(SELECT 1
+                                   FROM user_roles
+                                            JOIN roles ON roles.id = user_roles.role
+                                   WHERE user_roles.user = users.id
+                                     AND roles.name = 'Moderator'), 'true', 'false'),
+                         ',"submit":',
+                         IF(EXISTS(SELECT 1
+                                   FROM user_roles
+                                            JOIN roles ON roles.id = user_roles.role
+                                   WHERE user_roles.user = users.id
+                                     AND roles.name IN ('Recruiter', 'Admin')), 'true', 'false'),
+                         ',"review_user":',
+                         IF(EXISTS(SELECT 1
+                                   FROM user_roles
+                                            JOIN roles ON roles.id = user_roles.role
+                                   WHERE user_roles.user = users.id
+                                     AND roles.name IN ('Reviewer', 'Moderator', 'Admin')), 'true', 'false'),
+                         ',"login":',
+                         IF(EXISTS(SELECT 1
+                                   FROM user_roles
+                                            JOIN roles ON roles.id = user_roles.role
+                                   WHERE user_roles.user = users.id
+                                     AND roles.name IN ('Member', 'Admin')), 'true', 'false'),
+                         '}');
+
+DROP TABLE user_roles;
+DROP TABLE roles;
\ No newline at end of file
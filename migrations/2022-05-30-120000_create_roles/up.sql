@@ -0,0 +1,70 @@
+CREATE TABLE roles
+(
+    id          BIGINT AUTO_INCREMENT PRIMARY KEY,
+    name        VARCHAR(64) NOT NULL UNIQUE,
+    permissions TEXT        NOT NULL,
+    is_default  BOOLEAN     NOT NULL DEFAULT FALSE,
+    created     BIGINT      NOT NULL
+
+);
+
+CREATE TABLE user_roles
+(
+    id      BIGINT AUTO_INCREMENT PRIMARY KEY,
+    user    BIGINT NOT NULL,
+    role    BIGINT NOT NULL,
+    created BIGINT NOT NULL,
+    UNIQUE KEY user_roles_user_role (user, role)
+
+);
+
+-- New users used to get login and submit. The default roles are assigned to new users
+INSERT INTO roles (name, permissions, is_default, created)
+VALUES ('Member', '["login"]', TRUE, UNIX_TIMESTAMP() * 1000),
+       ('Recruiter', '["submit"]', TRUE, UNIX_TIMESTAMP() * 1000),
+       ('Reviewer', '["review.approve","review.deny"]', FALSE, UNIX_TIMESTAMP() * 1000),
+       ('Moderator',
+        '["review.approve","review.deny","review.remove","users.view","users.edit","claims.release"]',
+        FALSE, UNIX_TIMESTAMP() * 1000),
+       ('Admin',
+        '["login","submit","review.approve","review.deny","review.remove","users.view","users.edit","claims.release","team.edit","settings.edit","titles.edit","roles.edit","discovery.run","audit.view"]',
+        FALSE, UNIX_TIMESTAMP() * 1000);
+
+-- Only the users that could log in or submit keep it
+INSERT INTO user_roles (user, role, created)
+SELECT users.id, roles.id, UNIX_TIMESTAMP() * 1000
+FROM users,
+     roles
+WHERE roles.name = 'Member'
+  AND JSON_UNQUOTE(JSON_EXTRACT(users.permissions, '$.login')) = 'true';
+
+INSERT INTO user_roles (user, role, created)
+SELECT users.id, roles.id, UNIX_TIMESTAMP() * 1000
+FROM users,
+     roles
+WHERE roles.name = 'Recruiter'
+  AND JSON_UNQUOTE(JSON_EXTRACT(users.permissions, '$.submit')) = 'true';
+
+INSERT INTO user_roles (user, role, created)
+SELECT users.id, roles.id, UNIX_TIMESTAMP() * 1000
+FROM users,
+     roles
+WHERE roles.name = 'Reviewer'
+  AND JSON_UNQUOTE(JSON_EXTRACT(users.permissions, '$.review_user')) = 'true';
+
+INSERT INTO user_roles (user, role, created)
+SELECT users.id, roles.id, UNIX_TIMESTAMP() * 1000
+FROM users,
+     roles
+WHERE roles.name = 'Moderator'
+  AND JSON_UNQUOTE(JSON_EXTRACT(users.permissions, '$.moderator')) = 'true';
+
+INSERT INTO user_roles (user, role, created)
+SELECT users.id, roles.id, UNIX_TIMESTAMP() * 1000
+FROM users,
+     roles
+WHERE roles.name = 'Admin'
+  AND JSON_UNQUOTE(JSON_EXTRACT(users.permissions, '$.admin')) = 'true';
+
+ALTER TABLE users
+    DROP COLUMN permissions;
@@ -0,0 +1,4 @@
+ALTER TABLE auth_tokens
+    DROP COLUMN expiration,
+    DROP COLUMN last_used,
+    DROP COLUMN user_agent;
\ No newline at end of file
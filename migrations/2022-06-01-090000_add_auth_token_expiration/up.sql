@@ -0,0 +1,9 @@
+ALTER TABLE auth_tokens
+    ADD COLUMN expiration BIGINT       NOT NULL DEFAULT 0,
+    ADD COLUMN last_used  BIGINT       NOT NULL DEFAULT 0,
+    ADD COLUMN user_agent VARCHAR(255) DEFAULT NULL;
+
+-- Tokens used to live forever. Give them the default week from when they were made
+UPDATE auth_tokens
+SET expiration = created + 604800000,
+    last_used  = created;
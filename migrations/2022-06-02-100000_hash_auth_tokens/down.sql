@@ -0,0 +1,6 @@
+-- Hashed tokens can not be turned back into plaintext
+DELETE
+FROM auth_tokens;
+ALTER TABLE auth_tokens
+    DROP INDEX auth_tokens_selector,
+    DROP COLUMN selector;
\ No newline at end of file
@@ -0,0 +1,8 @@
+-- Tokens without a selector are hashed on startup. The key is not in the database
+ALTER TABLE auth_tokens
+    ADD COLUMN selector VARCHAR(12) NOT NULL DEFAULT '' AFTER user,
+    ADD INDEX auth_tokens_selector (selector);
+
+-- One time passwords only live for an hour. The plaintext ones are dropped instead of hashed
+DELETE
+FROM otps;
@@ -0,0 +1 @@
+DROP TABLE otp_attempts;
\ No newline at end of file
@@ -0,0 +1,10 @@
+CREATE TABLE otp_attempts
+(
+    id      BIGINT AUTO_INCREMENT PRIMARY KEY,
+    user    BIGINT      NOT NULL,
+    ip      VARCHAR(64) NOT NULL,
+    created BIGINT      NOT NULL,
+    INDEX otp_attempts_user (user),
+    INDEX otp_attempts_ip (ip)
+
+)
\ No newline at end of file
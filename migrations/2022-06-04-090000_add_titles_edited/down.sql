@@ -0,0 +1 @@
+ALTER TABLE titles DROP COLUMN edited;
\ No newline at end of file
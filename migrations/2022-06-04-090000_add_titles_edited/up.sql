@@ -0,0 +1,7 @@
+ALTER TABLE titles
+    ADD COLUMN edited BOOLEAN NOT NULL DEFAULT FALSE AFTER retired;
+
+-- Titles changed since they were added may have been changed by hand
+UPDATE titles
+SET edited = TRUE
+WHERE updated <> created;
@@ -1,21 +1,8 @@
-use crate::user::models::{TeamMember, UserPermissions};
+use crate::user::models::TeamMember;
 use diesel::prelude::*;
 use diesel::result::Error as DieselError;
 use diesel::MysqlConnection;
 
-pub fn set_permissions(
-    user: &i64,
-    perms: UserPermissions,
-    conn: &MysqlConnection,
-) -> Result<(), DieselError> {
-    use crate::schema::users::dsl::*;
-
-    diesel::update(users.filter(id.eq(user)))
-        .set(permissions.eq(&perms))
-        .execute(conn)?;
-    Ok(())
-}
-
 pub fn delete_team(i: &i64, conn: &MysqlConnection) -> Result<(), DieselError> {
     use crate::schema::team_members::dsl::*;
 
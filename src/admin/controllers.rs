@@ -2,57 +2,20 @@ use actix_web::{web, HttpRequest};
 
 use crate::admin::action::{add_new_team_member, delete_team, delete_team_user};
 use crate::api_response::{APIResponse, SiteResponse};
-use crate::error::response::{bad_request, not_found, unauthorized};
-use crate::user::action::{get_id_by_name, get_team_member, get_user_by_id};
+use crate::audit::models::AuditAction;
+use crate::audit::utils::audit;
+use crate::error::response::{bad_request, not_found};
+use crate::user::action::{delete_auth_tokens, get_id_by_name, get_team_member, get_user_by_id};
+use crate::user::authentication::{
+    Authenticated, RequireTeamEdit, RequireTitlesEdit, RequireUsersEdit,
+};
 use crate::user::models::{Level, TeamMember};
-use crate::user::utils::get_user_by_header;
-use crate::{get_current_time, Database};
+use crate::{get_current_time, Database, TitleData};
+use actix_web::http::StatusCode;
 use actix_web::{delete, post, put};
+use log::error;
 use serde::{Deserialize, Serialize};
 
-#[post("/api/admin/user/{user}/permission/{key}/{value}")]
-pub async fn update_permission(
-    database: Database,
-    r: HttpRequest,
-    path: web::Path<(i64, String, bool)>,
-) -> SiteResponse {
-    let (user, key, value) = path.into_inner();
-    let connection = database.get()?;
-
-    let admin = get_user_by_header(r.headers(), &connection)?;
-    if admin.is_none() || !admin.unwrap().permissions.admin {
-        return unauthorized();
-    }
-    let option = get_user_by_id(&user, &connection)?;
-    if option.is_none() {
-        return not_found();
-    }
-
-    let mut permissions = option.unwrap().permissions;
-    match key.as_str() {
-        "admin" => {
-            permissions.admin = value;
-        }
-        "moderator" => {
-            permissions.moderator = value;
-        }
-        "submit" => {
-            permissions.submit = value;
-        }
-        "review_user" => {
-            permissions.review_user = value;
-        }
-        "login" => {
-            permissions.login = value;
-        }
-        _ => {
-            return bad_request("Invalid Permission");
-        }
-    };
-    crate::admin::action::set_permissions(&user, permissions, &connection)?;
-    APIResponse::respond_new(Some(true), &r)
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewTeamMember {
     pub user: String,
@@ -63,20 +26,20 @@ pub struct NewTeamMember {
 #[put("/api/admin/team/add")]
 pub async fn add_team(
     database: Database,
+    admin: Authenticated<RequireTeamEdit>,
     r: HttpRequest,
     data: web::Json<NewTeamMember>,
 ) -> SiteResponse {
     let connection = database.get()?;
 
-    let admin = get_user_by_header(r.headers(), &connection)?;
-    if admin.is_none() || !admin.unwrap().permissions.admin {
-        return unauthorized();
-    }
     let option = get_id_by_name(&data.user, &connection)?;
     if option.is_none() {
         return bad_request("Invalid Username");
     }
     let user = option.unwrap();
+    let old = get_team_member(&user, &connection)?
+        .map(|member| serde_json::to_string(&member))
+        .transpose()?;
     delete_team_user(&user, &connection)?;
     let member = TeamMember {
         id: 0,
@@ -86,26 +49,99 @@ pub async fn add_team(
         created: get_current_time(),
     };
     add_new_team_member(&member, &connection)?;
+    audit(
+        &admin.username,
+        &data.user,
+        AuditAction::TeamAdd,
+        old,
+        Some(serde_json::to_string(&member)?),
+        &connection,
+    )?;
     APIResponse::respond_new(get_team_member(&member.user, &connection)?, &r)
 }
 
 #[delete("/api/admin/team/{member}")]
 pub async fn delete_team_member(
     database: Database,
+    admin: Authenticated<RequireTeamEdit>,
     r: HttpRequest,
     path: web::Path<i64>,
 ) -> SiteResponse {
     let team = path.into_inner();
     let connection = database.get()?;
 
-    let admin = get_user_by_header(r.headers(), &connection)?;
-    if admin.is_none() || !admin.unwrap().permissions.admin {
-        return unauthorized();
-    }
     let option = get_user_by_id(&team, &connection)?;
     if option.is_none() {
         return not_found();
     }
+    let old = get_team_member(&team, &connection)?
+        .map(|member| serde_json::to_string(&member))
+        .transpose()?;
     delete_team(&team, &connection)?;
+    audit(
+        &admin.username,
+        &option.unwrap().username,
+        AuditAction::TeamRemove,
+        old,
+        None,
+        &connection,
+    )?;
     APIResponse::respond_new(Some(true), &r)
 }
+
+/// Logs the user out everywhere
+#[delete("/api/admin/user/{user}/sessions")]
+pub async fn revoke_sessions(
+    database: Database,
+    admin: Authenticated<RequireUsersEdit>,
+    r: HttpRequest,
+    path: web::Path<i64>,
+) -> SiteResponse {
+    let user = path.into_inner();
+    let connection = database.get()?;
+    let option = get_user_by_id(&user, &connection)?;
+    if option.is_none() {
+        return not_found();
+    }
+    let revoked = delete_auth_tokens(&user, &connection)?;
+    audit(
+        &admin.username,
+        &option.unwrap().username,
+        AuditAction::SessionRevoke,
+        Some(revoked.to_string()),
+        None,
+        &connection,
+    )?;
+    APIResponse::respond_new(Some(revoked), &r)
+}
+
+#[post("/api/admin/titles/reload")]
+pub async fn reload_titles(
+    database: Database,
+    admin: Authenticated<RequireTitlesEdit>,
+    r: HttpRequest,
+    titles: TitleData,
+) -> SiteResponse {
+    let connection = database.get()?;
+
+    match titles.reload(&connection).await {
+        Ok(report) => {
+            audit(
+                &admin.username,
+                "titles",
+                AuditAction::TitleReload,
+                None,
+                Some(serde_json::to_string(&report)?),
+                &connection,
+            )?;
+            APIResponse::respond_new(Some(report), &r)
+        }
+        Err(error) => {
+            error!("Unable to Reload Titles {:?}", error);
+            crate::error::response::error(
+                "Unable to reload the titles. The current titles are still in use",
+                Some(StatusCode::BAD_GATEWAY),
+            )
+        }
+    }
+}
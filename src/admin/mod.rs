@@ -1,11 +1,29 @@
 mod action;
 mod controllers;
 mod models;
+mod role_controllers;
+mod title_controllers;
 
 use actix_web::web;
 
 pub fn init(cfg: &mut web::ServiceConfig) {
-    cfg.service(controllers::update_permission)
-        .service(controllers::delete_team_member)
-        .service(controllers::add_team);
+    cfg.service(controllers::delete_team_member)
+        .service(controllers::add_team)
+        .service(controllers::revoke_sessions)
+        .service(controllers::reload_titles);
+    // order, retitle and reload have to come before the edit route or they will be read as a title id
+    cfg.service(title_controllers::list_titles)
+        .service(title_controllers::order_titles)
+        .service(title_controllers::retitle)
+        .service(title_controllers::add_title_element)
+        .service(title_controllers::retire_title)
+        .service(title_controllers::edit_title_element);
+    cfg.service(role_controllers::list_permissions)
+        .service(role_controllers::list_roles)
+        .service(role_controllers::add_new_role)
+        .service(role_controllers::edit_role)
+        .service(role_controllers::remove_role)
+        .service(role_controllers::user_roles)
+        .service(role_controllers::assign_role)
+        .service(role_controllers::unassign_role);
 }
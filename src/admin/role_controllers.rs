@@ -0,0 +1,239 @@
+use actix_web::{delete, get, post, put, web, HttpRequest};
+use strum::IntoEnumIterator;
+
+use crate::api_response::{APIResponse, SiteResponse};
+use crate::audit::models::AuditAction;
+use crate::audit::utils::audit;
+use crate::error::response::{already_exists, bad_request, not_found};
+use crate::user::action::get_user_by_id;
+use crate::user::authentication::{Authenticated, RequireRolesEdit};
+use crate::user::role::action::{
+    add_role, add_user_role, delete_role, delete_user_role, get_all_user_roles, get_role,
+    get_role_by_name, get_roles, get_user_roles, has_user_role, update_role,
+};
+use crate::user::role::models::{has_role_editor, Permission, Role, RoleRequest, UserRole};
+use crate::{get_current_time, Database};
+
+/// Returned when a change would leave nobody with roles.edit
+const LOCKOUT: &str = "Nobody would be left to manage the roles";
+
+#[get("/api/admin/roles")]
+pub async fn list_roles(
+    database: Database,
+    _admin: Authenticated<RequireRolesEdit>,
+    r: HttpRequest,
+) -> SiteResponse {
+    let connection = database.get()?;
+    APIResponse::respond_new(Some(get_roles(&connection)?), &r)
+}
+
+/// Every permission a role can have
+#[get("/api/admin/roles/permissions")]
+pub async fn list_permissions(
+    _admin: Authenticated<RequireRolesEdit>,
+    r: HttpRequest,
+) -> SiteResponse {
+    let permissions: Vec<Permission> = Permission::iter().collect();
+    APIResponse::respond_new(Some(permissions), &r)
+}
+
+#[put("/api/admin/roles/add")]
+pub async fn add_new_role(
+    database: Database,
+    admin: Authenticated<RequireRolesEdit>,
+    r: HttpRequest,
+    data: web::Json<RoleRequest>,
+) -> SiteResponse {
+    let connection = database.get()?;
+    let request = data.into_inner();
+    let name = request.name.trim();
+    if name.is_empty() {
+        return bad_request("A Role needs a name");
+    }
+    if get_role_by_name(name, &connection)?.is_some() {
+        return already_exists();
+    }
+    let role = Role {
+        id: 0,
+        name: name.to_string(),
+        permissions: request.permissions,
+        is_default: request.is_default,
+        created: get_current_time(),
+    };
+    add_role(&role, &connection)?;
+    audit(
+        &admin.username,
+        &role.name,
+        AuditAction::RoleAdd,
+        None,
+        Some(serde_json::to_string(&role.permissions)?),
+        &connection,
+    )?;
+    APIResponse::respond_new(get_role_by_name(&role.name, &connection)?, &r)
+}
+
+#[post("/api/admin/roles/{role}")]
+pub async fn edit_role(
+    database: Database,
+    admin: Authenticated<RequireRolesEdit>,
+    r: HttpRequest,
+    path: web::Path<i64>,
+    data: web::Json<RoleRequest>,
+) -> SiteResponse {
+    let id = path.into_inner();
+    let connection = database.get()?;
+    let option = get_role(&id, &connection)?;
+    if option.is_none() {
+        return not_found();
+    }
+    let request = data.into_inner();
+    let name = request.name.trim();
+    if name.is_empty() {
+        return bad_request("A Role needs a name");
+    }
+    if let Some(existing) = get_role_by_name(name, &connection)? {
+        if existing.id != id {
+            return already_exists();
+        }
+    }
+    let mut roles = get_roles(&connection)?;
+    for role in roles.iter_mut().filter(|role| role.id == id) {
+        role.permissions = request.permissions.clone();
+    }
+    if !has_role_editor(&roles, &get_all_user_roles(&connection)?) {
+        return bad_request(LOCKOUT);
+    }
+    let mut role = option.unwrap();
+    let old = serde_json::to_string(&role)?;
+    role.name = name.to_string();
+    role.permissions = request.permissions;
+    role.is_default = request.is_default;
+    update_role(&role, &connection)?;
+    audit(
+        &admin.username,
+        &role.name,
+        AuditAction::RoleEdit,
+        Some(old),
+        Some(serde_json::to_string(&role)?),
+        &connection,
+    )?;
+    APIResponse::respond_new(get_role(&id, &connection)?, &r)
+}
+
+/// Deletes the role. Everyone holding it loses it
+#[delete("/api/admin/roles/{role}")]
+pub async fn remove_role(
+    database: Database,
+    admin: Authenticated<RequireRolesEdit>,
+    r: HttpRequest,
+    path: web::Path<i64>,
+) -> SiteResponse {
+    let id = path.into_inner();
+    let connection = database.get()?;
+    let option = get_role(&id, &connection)?;
+    if option.is_none() {
+        return not_found();
+    }
+    let role = option.unwrap();
+    let mut roles = get_roles(&connection)?;
+    roles.retain(|value| value.id != role.id);
+    if !has_role_editor(&roles, &get_all_user_roles(&connection)?) {
+        return bad_request(LOCKOUT);
+    }
+    delete_role(&role.id, &connection)?;
+    audit(
+        &admin.username,
+        &role.name,
+        AuditAction::RoleDelete,
+        Some(serde_json::to_string(&role)?),
+        None,
+        &connection,
+    )?;
+    APIResponse::respond_new(Some(true), &r)
+}
+
+/// The roles assigned to the user
+#[get("/api/admin/user/{user}/roles")]
+pub async fn user_roles(
+    database: Database,
+    _admin: Authenticated<RequireRolesEdit>,
+    r: HttpRequest,
+    path: web::Path<i64>,
+) -> SiteResponse {
+    let user = path.into_inner();
+    let connection = database.get()?;
+    if get_user_by_id(&user, &connection)?.is_none() {
+        return not_found();
+    }
+    APIResponse::respond_new(Some(get_user_roles(&user, &connection)?), &r)
+}
+
+#[put("/api/admin/user/{user}/roles/{role}")]
+pub async fn assign_role(
+    database: Database,
+    admin: Authenticated<RequireRolesEdit>,
+    r: HttpRequest,
+    path: web::Path<(i64, i64)>,
+) -> SiteResponse {
+    let (user, role) = path.into_inner();
+    let connection = database.get()?;
+    let target = get_user_by_id(&user, &connection)?;
+    let role = get_role(&role, &connection)?;
+    if target.is_none() || role.is_none() {
+        return not_found();
+    }
+    let (target, role) = (target.unwrap(), role.unwrap());
+    if has_user_role(&target.id, &role.id, &connection)? {
+        return already_exists();
+    }
+    let value = UserRole {
+        id: 0,
+        user: target.id,
+        role: role.id,
+        created: get_current_time(),
+    };
+    add_user_role(&value, &connection)?;
+    audit(
+        &admin.username,
+        &target.username,
+        AuditAction::RoleAssign,
+        None,
+        Some(role.name),
+        &connection,
+    )?;
+    APIResponse::respond_new(Some(true), &r)
+}
+
+#[delete("/api/admin/user/{user}/roles/{role}")]
+pub async fn unassign_role(
+    database: Database,
+    admin: Authenticated<RequireRolesEdit>,
+    r: HttpRequest,
+    path: web::Path<(i64, i64)>,
+) -> SiteResponse {
+    let (user, role) = path.into_inner();
+    let connection = database.get()?;
+    let target = get_user_by_id(&user, &connection)?;
+    let role = get_role(&role, &connection)?;
+    if target.is_none() || role.is_none() {
+        return not_found();
+    }
+    let (target, role) = (target.unwrap(), role.unwrap());
+    let mut assigned = get_all_user_roles(&connection)?;
+    assigned.retain(|value| value.user != target.id || value.role != role.id);
+    if !has_role_editor(&get_roles(&connection)?, &assigned) {
+        return bad_request(LOCKOUT);
+    }
+    if delete_user_role(&target.id, &role.id, &connection)? == 0 {
+        return not_found();
+    }
+    audit(
+        &admin.username,
+        &target.username,
+        AuditAction::RoleUnassign,
+        Some(role.name),
+        None,
+        &connection,
+    )?;
+    APIResponse::respond_new(Some(true), &r)
+}
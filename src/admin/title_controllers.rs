@@ -0,0 +1,188 @@
+use actix_web::{get, post, put, web, HttpRequest};
+use serde::{Deserialize, Serialize};
+
+use crate::api_response::{APIResponse, SiteResponse};
+use crate::audit::models::AuditAction;
+use crate::audit::utils::audit;
+use crate::error::response::{already_exists, bad_request, not_found};
+use crate::user::authentication::{Authenticated, RequireTitlesEdit};
+use crate::user::title::action::{
+    add_title, get_title, get_title_by_value, get_titles, next_ordering, set_ordering, set_retired,
+    update_title_element,
+};
+use crate::user::title::models::DBTitle;
+use crate::user::title::retitle::retitle_users;
+use crate::user::title::TitleElement;
+use crate::{get_current_time, Database, TitleData};
+
+/// Every title including the retired ones
+#[get("/api/admin/titles")]
+pub async fn list_titles(
+    database: Database,
+    _admin: Authenticated<RequireTitlesEdit>,
+    r: HttpRequest,
+) -> SiteResponse {
+    let connection = database.get()?;
+
+    APIResponse::respond_new(Some(get_titles(true, &connection)?), &r)
+}
+
+#[put("/api/admin/titles/add")]
+pub async fn add_title_element(
+    database: Database,
+    admin: Authenticated<RequireTitlesEdit>,
+    r: HttpRequest,
+    titles: TitleData,
+    data: web::Json<TitleElement>,
+) -> SiteResponse {
+    let connection = database.get()?;
+
+    let element = data.into_inner();
+    if element.value.trim().is_empty() {
+        return bad_request("A Title needs a value");
+    }
+    if get_title_by_value(&element.value.to_lowercase(), &connection)?.is_some() {
+        return already_exists();
+    }
+    let new = serde_json::to_string(&element)?;
+    let mut title = DBTitle::new(element, next_ordering(&connection)?, get_current_time());
+    title.edited = true;
+    add_title(&title, &connection)?;
+    audit(
+        &admin.username,
+        &title.value,
+        AuditAction::TitleAdd,
+        None,
+        Some(new),
+        &connection,
+    )?;
+    titles.refresh(&connection)?;
+    APIResponse::respond_new(get_title_by_value(&title.value, &connection)?, &r)
+}
+
+#[post("/api/admin/titles/{title}")]
+pub async fn edit_title_element(
+    database: Database,
+    admin: Authenticated<RequireTitlesEdit>,
+    r: HttpRequest,
+    titles: TitleData,
+    path: web::Path<i64>,
+    data: web::Json<TitleElement>,
+) -> SiteResponse {
+    let id = path.into_inner();
+    let connection = database.get()?;
+
+    let option = get_title(&id, &connection)?;
+    if option.is_none() {
+        return not_found();
+    }
+    let existing = option.unwrap();
+    let element = data.into_inner();
+    if element.value.trim().is_empty() {
+        return bad_request("A Title needs a value");
+    }
+    if let Some(other) = get_title_by_value(&element.value.to_lowercase(), &connection)? {
+        if other.id != existing.id {
+            return already_exists();
+        }
+    }
+    let new = serde_json::to_string(&element)?;
+    let old = serde_json::to_string(&TitleElement::from(existing.clone()))?;
+    let mut title = DBTitle::new(element, existing.ordering, existing.created);
+    title.id = existing.id;
+    title.edited = true;
+    update_title_element(&title, &connection)?;
+    audit(
+        &admin.username,
+        &existing.value,
+        AuditAction::TitleEdit,
+        Some(old),
+        Some(new),
+        &connection,
+    )?;
+    titles.refresh(&connection)?;
+    APIResponse::respond_new(get_title(&id, &connection)?, &r)
+}
+
+#[post("/api/admin/titles/{title}/retire/{value}")]
+pub async fn retire_title(
+    database: Database,
+    admin: Authenticated<RequireTitlesEdit>,
+    r: HttpRequest,
+    titles: TitleData,
+    path: web::Path<(i64, bool)>,
+) -> SiteResponse {
+    let (id, value) = path.into_inner();
+    let connection = database.get()?;
+
+    let option = get_title(&id, &connection)?;
+    if option.is_none() {
+        return not_found();
+    }
+    let existing = option.unwrap();
+    set_retired(&id, value, &connection)?;
+    audit(
+        &admin.username,
+        &existing.value,
+        AuditAction::TitleRetire,
+        Some(existing.retired.to_string()),
+        Some(value.to_string()),
+        &connection,
+    )?;
+    titles.refresh(&connection)?;
+    APIResponse::respond_new(get_title(&id, &connection)?, &r)
+}
+
+/// Takes the ids of the titles in the order they should be in
+#[post("/api/admin/titles/order")]
+pub async fn order_titles(
+    database: Database,
+    admin: Authenticated<RequireTitlesEdit>,
+    r: HttpRequest,
+    titles: TitleData,
+    data: web::Json<Vec<i64>>,
+) -> SiteResponse {
+    let connection = database.get()?;
+
+    set_ordering(&data, &connection)?;
+    audit(
+        &admin.username,
+        "titles",
+        AuditAction::TitleOrder,
+        None,
+        Some(serde_json::to_string(&data.0)?),
+        &connection,
+    )?;
+    titles.refresh(&connection)?;
+    APIResponse::respond_new(Some(get_titles(true, &connection)?), &r)
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct RetitleQuery {
+    pub apply: Option<bool>,
+}
+
+/// Reapplies the titles to every user. Only reports the changes unless `apply=true` is passed
+#[post("/api/admin/titles/retitle")]
+pub async fn retitle(
+    database: Database,
+    admin: Authenticated<RequireTitlesEdit>,
+    r: HttpRequest,
+    titles: TitleData,
+) -> SiteResponse {
+    let connection = database.get()?;
+
+    let query: RetitleQuery = serde_qs::from_str(r.query_string()).unwrap_or_default();
+    let report = retitle_users(&titles.get(), query.apply.unwrap_or(false), &connection)?;
+    if report.applied {
+        audit(
+            &admin.username,
+            "users",
+            AuditAction::Retitle,
+            None,
+            Some(report.updated.to_string()),
+            &connection,
+        )?;
+    }
+    APIResponse::respond_new(Some(report), &r)
+}
@@ -0,0 +1,54 @@
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use diesel::MysqlConnection;
+
+use crate::audit::models::{AuditAction, AuditEvent};
+
+pub fn add_audit_event(event: &AuditEvent, conn: &MysqlConnection) -> Result<(), DieselError> {
+    use crate::schema::audit_events::dsl::*;
+    diesel::insert_into(audit_events)
+        .values(event)
+        .execute(conn)?;
+    Ok(())
+}
+
+/// What the audit log can be filtered by. Every filter is optional
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub target: Option<String>,
+    pub action: Option<AuditAction>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+}
+
+/// The newest events first
+pub fn get_audit_events(
+    filter: &AuditFilter,
+    page: i64,
+    per_page: i64,
+    conn: &MysqlConnection,
+) -> Result<Vec<AuditEvent>, DieselError> {
+    use crate::schema::audit_events::dsl::*;
+    let mut query = audit_events.into_boxed();
+    if let Some(value) = &filter.actor {
+        query = query.filter(actor.eq(value));
+    }
+    if let Some(value) = &filter.target {
+        query = query.filter(target.eq(value));
+    }
+    if let Some(value) = &filter.action {
+        query = query.filter(action.eq(value));
+    }
+    if let Some(value) = filter.since {
+        query = query.filter(created.ge(value));
+    }
+    if let Some(value) = filter.until {
+        query = query.filter(created.le(value));
+    }
+    query
+        .order((created.desc(), id.desc()))
+        .limit(per_page)
+        .offset(page * per_page)
+        .load::<AuditEvent>(conn)
+}
@@ -0,0 +1,49 @@
+use actix_web::{get, HttpRequest};
+use serde::{Deserialize, Serialize};
+
+use crate::api_response::{APIResponse, SiteResponse};
+use crate::audit::action::{get_audit_events, AuditFilter};
+use crate::audit::models::AuditAction;
+use crate::error::response::bad_request;
+use crate::user::authentication::{Authenticated, RequireAuditView};
+use crate::Database;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct AuditQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    pub actor: Option<String>,
+    pub target: Option<String>,
+    pub action: Option<AuditAction>,
+    //Milliseconds
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+}
+
+#[get("/api/admin/audit")]
+pub async fn get_audit(
+    database: Database,
+    _admin: Authenticated<RequireAuditView>,
+    r: HttpRequest,
+) -> SiteResponse {
+    let connection = database.get()?;
+
+    let query: AuditQuery = match serde_qs::from_str(r.query_string()) {
+        Ok(query) => query,
+        Err(_) => return bad_request("Invalid Audit Query"),
+    };
+    let filter = AuditFilter {
+        actor: query.actor,
+        target: query.target,
+        action: query.action,
+        since: query.since,
+        until: query.until,
+    };
+    let per_page = query.per_page.unwrap_or(50).clamp(1, 500);
+    let page = query.page.unwrap_or(0).max(0);
+    if page.checked_mul(per_page).is_none() {
+        return bad_request("Page is too big");
+    }
+    let events = get_audit_events(&filter, page, per_page, &connection)?;
+    APIResponse::respond_new(Some(events), &r)
+}
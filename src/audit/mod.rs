@@ -0,0 +1,10 @@
+pub mod action;
+mod controllers;
+pub mod models;
+pub mod utils;
+
+use actix_web::web;
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(controllers::get_audit);
+}
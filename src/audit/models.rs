@@ -0,0 +1,78 @@
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize::FromSql;
+use diesel::mysql::Mysql;
+use diesel::serialize::{Output, ToSql};
+use diesel::sql_types::Text;
+use diesel::{deserialize, serialize};
+use log::error;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+use crate::schema::*;
+
+/// Something a user did that changed the state of the site
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "audit_events"]
+pub struct AuditEvent {
+    pub id: i64,
+    //The username of who did it. SYSTEM if it was not done by a user
+    pub actor: String,
+    //The username, setting key or title that was changed
+    pub target: String,
+    pub action: AuditAction,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub created: i64,
+}
+
+#[derive(
+    AsExpression, Debug, Deserialize, Serialize, FromSqlRow, Clone, Display, PartialEq, EnumString,
+)]
+#[sql_type = "Text"]
+pub enum AuditAction {
+    Submit,
+    StatusChange,
+    TitleChange,
+    PropertyChange,
+    PasswordChange,
+    PermissionChange,
+    TeamAdd,
+    TeamRemove,
+    SettingChange,
+    TitleAdd,
+    TitleEdit,
+    TitleRetire,
+    TitleOrder,
+    TitleReload,
+    Retitle,
+    RoleAdd,
+    RoleEdit,
+    RoleDelete,
+    RoleAssign,
+    RoleUnassign,
+    SessionRevoke,
+}
+
+impl ToSql<Text, Mysql> for AuditAction {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Mysql>) -> serialize::Result {
+        let s = self.to_string();
+        <String as ToSql<Text, Mysql>>::to_sql(&s, out)
+    }
+}
+
+impl FromSql<Text, Mysql> for AuditAction {
+    fn from_sql(
+        bytes: Option<&<diesel::mysql::Mysql as Backend>::RawValue>,
+    ) -> deserialize::Result<AuditAction> {
+        let t = <String as FromSql<Text, Mysql>>::from_sql(bytes)?;
+        let result: Result<AuditAction, strum::ParseError> = AuditAction::from_str(t.as_str());
+        if let Err(error) = result {
+            error!("Unable to Parse AuditAction {} Value {}", error, t);
+            return Err(Box::new(error));
+        }
+        Ok(result.unwrap())
+    }
+}
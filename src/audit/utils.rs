@@ -0,0 +1,30 @@
+use diesel::result::Error as DieselError;
+use diesel::MysqlConnection;
+
+use crate::audit::action::add_audit_event;
+use crate::audit::models::{AuditAction, AuditEvent};
+use crate::utils::get_current_time;
+
+/// The actor used for changes that were not made by a user
+pub const SYSTEM: &str = "SYSTEM";
+
+/// Records a change in the audit log
+pub fn audit(
+    actor: &str,
+    target: &str,
+    action: AuditAction,
+    old_value: Option<String>,
+    new_value: Option<String>,
+    conn: &MysqlConnection,
+) -> Result<(), DieselError> {
+    let event = AuditEvent {
+        id: 0,
+        actor: actor.to_string(),
+        target: target.to_string(),
+        action,
+        old_value,
+        new_value,
+        created: get_current_time(),
+    };
+    add_audit_event(&event, conn)
+}
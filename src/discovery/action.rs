@@ -0,0 +1,26 @@
+use crate::discovery::models::DiscoveryRun;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use diesel::MysqlConnection;
+
+pub fn add_discovery_run(run: &DiscoveryRun, conn: &MysqlConnection) -> Result<(), DieselError> {
+    use crate::schema::discovery_runs::dsl::*;
+    diesel::insert_into(discovery_runs)
+        .values(run)
+        .execute(conn)?;
+    Ok(())
+}
+
+/// The newest runs first
+pub fn get_discovery_runs(
+    page: i64,
+    per_page: i64,
+    conn: &MysqlConnection,
+) -> Result<Vec<DiscoveryRun>, DieselError> {
+    use crate::schema::discovery_runs::dsl::*;
+    discovery_runs
+        .order(started.desc())
+        .limit(per_page)
+        .offset(page * per_page)
+        .load::<DiscoveryRun>(conn)
+}
@@ -0,0 +1,65 @@
+use actix_web::http::StatusCode;
+use actix_web::{get, post, HttpRequest};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+use crate::api_response::{APIResponse, SiteResponse};
+use crate::discovery::action::get_discovery_runs;
+use crate::discovery::{is_running, run_discovery};
+use crate::error::response::bad_request;
+use crate::user::authentication::{Authenticated, RequireDiscoveryRun};
+use crate::{Database, TitleData};
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct RunsQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[get("/api/admin/discovery/runs")]
+pub async fn get_runs(
+    database: Database,
+    _admin: Authenticated<RequireDiscoveryRun>,
+    r: HttpRequest,
+) -> SiteResponse {
+    let connection = database.get()?;
+
+    let query: RunsQuery = serde_qs::from_str(r.query_string()).unwrap_or_default();
+    let per_page = query.per_page.unwrap_or(50).clamp(1, 500);
+    let page = query.page.unwrap_or(0).max(0);
+    if page.checked_mul(per_page).is_none() {
+        return bad_request("Page is too big");
+    }
+    let runs = get_discovery_runs(page, per_page, &connection)?;
+    APIResponse::respond_new(Some(runs), &r)
+}
+
+#[post("/api/admin/discovery/run-now")]
+pub async fn run_now(
+    database: Database,
+    _admin: Authenticated<RequireDiscoveryRun>,
+    r: HttpRequest,
+    titles: TitleData,
+) -> SiteResponse {
+    if is_running() {
+        return crate::error::response::error(
+            "Discovery is already running",
+            Some(StatusCode::CONFLICT),
+        );
+    }
+    let pool = database.get_ref().clone();
+    let titles = titles.get();
+    actix_web::rt::spawn(async move {
+        match run_discovery(&pool, &titles).await {
+            Ok(Some(runs)) => info!("Manual Discovery finished {} sources", runs.len()),
+            Ok(None) => info!("Discovery is already running"),
+            Err(error) => error!("Manual Discovery failed {:?}", error),
+        }
+    });
+    APIResponse {
+        success: true,
+        data: Some(true),
+        status_code: Some(202),
+    }
+    .respond(&r)
+}
@@ -0,0 +1,176 @@
+pub mod action;
+mod controllers;
+pub mod models;
+pub mod sources;
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use actix_web::web;
+use diesel::MysqlConnection;
+use futures::future::LocalBoxFuture;
+use log::{error, info};
+use rraw::auth::AnonymousAuthenticator;
+use rraw::Client;
+
+use crate::discovery::action::add_discovery_run;
+use crate::discovery::models::DiscoveryRun;
+use crate::error::internal_error::InternalError;
+use crate::settings::utils::get_setting_value;
+use crate::user::utils::quick_add;
+use crate::user::title::matcher::match_titles;
+use crate::utils::get_current_time;
+use crate::{DbPool, TitleData, Titles};
+
+/// The discoverer of every user found by the bot
+pub const BOT: &str = "Bot";
+
+/// Set while a discovery run is going so the admin panel can't start a second one
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(controllers::get_runs)
+        .service(controllers::run_now);
+}
+
+pub type DiscoveryClient = Client<AnonymousAuthenticator>;
+
+/// The usernames a source found
+#[derive(Debug, Default)]
+pub struct SourceUsernames {
+    pub usernames: Vec<String>,
+    //Parts of the source that could not be read. The rest of the source is still used
+    pub errors: i64,
+}
+
+impl From<Vec<String>> for SourceUsernames {
+    fn from(usernames: Vec<String>) -> Self {
+        SourceUsernames {
+            usernames,
+            errors: 0,
+        }
+    }
+}
+
+/// A place on Reddit the bot can find usernames
+pub trait DiscoverySource {
+    /// The name used to identify the source in the logs
+    fn name(&self) -> String;
+    /// Every username the source can currently see. Duplicates are removed by the pipeline
+    fn usernames<'a>(
+        &'a self,
+        client: &'a DiscoveryClient,
+    ) -> LocalBoxFuture<'a, Result<SourceUsernames, InternalError>>;
+}
+
+/// Runs a single source and adds every new username with a title
+pub async fn run_source(
+    source: &dyn DiscoverySource,
+    client: &DiscoveryClient,
+    conn: &MysqlConnection,
+    titles: &Titles,
+) -> DiscoveryRun {
+    let mut report = DiscoveryRun {
+        source: source.name(),
+        started: get_current_time(),
+        ..Default::default()
+    };
+    let usernames = match source.usernames(client).await {
+        Ok(value) => {
+            report.errors += value.errors;
+            value.usernames
+        }
+        Err(error) => {
+            error!("Discovery Source {} failed {:?}", &report.source, error);
+            report.errors += 1;
+            report.finished = get_current_time();
+            return report;
+        }
+    };
+    let mut seen = HashSet::new();
+    for username in usernames {
+        if username.eq("[deleted]") || !seen.insert(username.to_lowercase()) {
+            continue;
+        }
+        report.scanned += 1;
+        if match_titles(&username, titles).is_empty() {
+            continue;
+        }
+        report.matched += 1;
+        match quick_add(&username, BOT, conn, titles) {
+            Ok(true) => report.inserted += 1,
+            Ok(false) => {}
+            Err(error) => {
+                error!("Unable to add {} {:?}", &username, error);
+                report.errors += 1;
+            }
+        }
+    }
+    report.finished = get_current_time();
+    info!(
+        "Discovery Source {} scanned {} matched {} inserted {}",
+        &report.source, report.scanned, report.matched, report.inserted
+    );
+    report
+}
+
+pub fn is_running() -> bool {
+    RUNNING.load(Ordering::SeqCst)
+}
+
+/// Clears RUNNING when dropped so a run that fails, panics or is cancelled does not block the next one
+struct RunningGuard;
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Runs every configured source once and saves the results. Returns None if a run is already going
+pub async fn run_discovery(
+    pool: &DbPool,
+    titles: &Titles,
+) -> Result<Option<Vec<DiscoveryRun>>, InternalError> {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Ok(None);
+    }
+    let _guard = RunningGuard;
+    run_sources(pool, titles).await.map(Some)
+}
+
+async fn run_sources(pool: &DbPool, titles: &Titles) -> Result<Vec<DiscoveryRun>, InternalError> {
+    let conn = pool.get()?;
+    let sources = sources::load_sources(&conn)?;
+    let client = Client::login(
+        AnonymousAuthenticator::new(),
+        "RedditNobility bot(by u/KingTuxWH)".to_string(),
+    )
+    .await?;
+    let mut runs = Vec::new();
+    for source in sources {
+        let run = run_source(source.as_ref(), &client, &conn, titles).await;
+        add_discovery_run(&run, &conn)?;
+        runs.push(run);
+    }
+    Ok(runs)
+}
+
+/// Runs discovery forever waiting `discovery.interval` seconds between runs
+pub async fn discovery_loop(pool: DbPool, titles: TitleData) {
+    loop {
+        info!("Starting Discovery");
+        match run_discovery(&pool, &titles.get()).await {
+            Ok(None) => info!("Discovery is already running"),
+            Err(error) => error!("Discovery failed {:?}", error),
+            _ => {}
+        }
+        let interval = pool
+            .get()
+            .map_err(InternalError::from)
+            .and_then(|conn| get_setting_value::<u64>("discovery.interval", &conn))
+            .unwrap_or(7200);
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+    }
+}
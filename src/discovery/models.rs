@@ -0,0 +1,19 @@
+use crate::schema::*;
+use serde::{Deserialize, Serialize};
+
+/// The result of running a single discovery source
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable, Default)]
+#[table_name = "discovery_runs"]
+pub struct DiscoveryRun {
+    pub id: i64,
+    pub source: String,
+    pub started: i64,
+    pub finished: i64,
+    //Unique usernames seen
+    pub scanned: i64,
+    //Usernames with a title
+    pub matched: i64,
+    //Usernames that were not in the database yet
+    pub inserted: i64,
+    pub errors: i64,
+}
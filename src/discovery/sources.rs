@@ -0,0 +1,156 @@
+use std::fs::read_to_string;
+use std::time::Duration;
+
+use diesel::MysqlConnection;
+use futures::future::LocalBoxFuture;
+use log::{error, warn};
+use rraw::responses::RedditTypeResponse;
+use rraw::submission::response::SubmissionsResponse;
+
+use crate::discovery::{DiscoveryClient, DiscoverySource, SourceUsernames};
+use crate::error::internal_error::InternalError;
+use crate::settings::utils::get_setting_value;
+
+/// The authors of a subreddit feed. Sort can be hot, new or rising
+pub struct SubredditFeed {
+    pub subreddit: String,
+    pub sort: String,
+}
+
+impl DiscoverySource for SubredditFeed {
+    fn name(&self) -> String {
+        format!("{}:{}", self.sort, self.subreddit)
+    }
+
+    fn usernames<'a>(
+        &'a self,
+        client: &'a DiscoveryClient,
+    ) -> LocalBoxFuture<'a, Result<SourceUsernames, InternalError>> {
+        Box::pin(async move {
+            let subreddit = client.subreddit(self.subreddit.as_str()).await?;
+            let submissions: SubmissionsResponse =
+                subreddit.get_submissions(self.sort.as_str(), None).await?;
+            let usernames: Vec<String> = submissions
+                .data
+                .children
+                .into_iter()
+                .map(|submission| submission.data.author)
+                .collect();
+            Ok(usernames.into())
+        })
+    }
+}
+
+/// The commenters on the hot posts of a subreddit
+pub struct CommentStream {
+    pub subreddit: String,
+    //Time to wait between each post so we don't hit the rate limit
+    pub delay: Duration,
+}
+
+impl DiscoverySource for CommentStream {
+    fn name(&self) -> String {
+        format!("comments:{}", self.subreddit)
+    }
+
+    fn usernames<'a>(
+        &'a self,
+        client: &'a DiscoveryClient,
+    ) -> LocalBoxFuture<'a, Result<SourceUsernames, InternalError>> {
+        Box::pin(async move {
+            let subreddit = client.subreddit(self.subreddit.as_str()).await?;
+            let submissions: SubmissionsResponse = subreddit.get_submissions("hot", None).await?;
+            let mut found = SourceUsernames::default();
+            for submission_response in submissions.data.children.iter() {
+                let submission = submission_response.data.to_submission(client);
+                // One post failing should not throw away the commenters of the others
+                let comments = match submission.get_comments(None).await {
+                    Ok(comments) => comments,
+                    Err(error) => {
+                        error!(
+                            "Unable to load the comments of {} {:?}",
+                            &submission_response.data.id, error
+                        );
+                        found.errors += 1;
+                        tokio::time::sleep(self.delay).await;
+                        continue;
+                    }
+                };
+                if let Some(listing) = comments.get(1) {
+                    for comment in listing.data.children.iter() {
+                        if let RedditTypeResponse::Comment(comment) = &comment.data {
+                            if let Some(author) = &comment.author {
+                                found.usernames.push(author.clone());
+                            }
+                        }
+                    }
+                }
+                tokio::time::sleep(self.delay).await;
+            }
+            Ok(found)
+        })
+    }
+}
+
+/// A file of usernames. One per line
+pub struct UserList {
+    pub path: String,
+}
+
+impl DiscoverySource for UserList {
+    fn name(&self) -> String {
+        format!("list:{}", self.path)
+    }
+
+    fn usernames<'a>(
+        &'a self,
+        _client: &'a DiscoveryClient,
+    ) -> LocalBoxFuture<'a, Result<SourceUsernames, InternalError>> {
+        Box::pin(async move {
+            let content = read_to_string(&self.path)?;
+            let usernames: Vec<String> = content
+                .lines()
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect();
+            Ok(usernames.into())
+        })
+    }
+}
+
+/// Parses a single source such as `hot:all`, `comments:AskReddit` or `list:users.txt`
+pub fn parse_source(value: &str, delay: Duration) -> Option<Box<dyn DiscoverySource>> {
+    let (kind, target) = value.split_once(':')?;
+    let target = target.trim().to_string();
+    if target.is_empty() {
+        return None;
+    }
+    match kind.trim() {
+        "hot" | "new" | "rising" => Some(Box::new(SubredditFeed {
+            subreddit: target,
+            sort: kind.trim().to_string(),
+        })),
+        "comments" => Some(Box::new(CommentStream {
+            subreddit: target,
+            delay,
+        })),
+        "list" => Some(Box::new(UserList { path: target })),
+        _ => None,
+    }
+}
+
+/// Loads the sources from the comma separated `discovery.sources` setting
+pub fn load_sources(
+    conn: &MysqlConnection,
+) -> Result<Vec<Box<dyn DiscoverySource>>, InternalError> {
+    let value: String = get_setting_value("discovery.sources", conn)?;
+    let delay = Duration::from_secs(get_setting_value("discovery.delay", conn)?);
+    let mut sources = Vec::new();
+    for entry in value.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        match parse_source(entry, delay) {
+            Some(source) => sources.push(source),
+            None => warn!("Unknown Discovery Source {}", entry),
+        }
+    }
+    Ok(sources)
+}
@@ -2,14 +2,12 @@ use std::str::ParseBoolError;
 
 use actix_web::HttpResponse;
 
-use crate::RNCore;
 use actix_web::http::StatusCode;
 use base64::DecodeError;
 use log::error;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::string::FromUtf8Error;
-use std::sync::PoisonError;
 
 #[derive(Debug)]
 pub enum InternalError {
@@ -76,12 +74,6 @@ impl From<rraw::error::http_error::HTTPError> for InternalError {
     }
 }
 
-impl From<PoisonError<std::sync::MutexGuard<'_, RNCore>>> for InternalError {
-    fn from(_err: PoisonError<std::sync::MutexGuard<'_, RNCore>>) -> InternalError {
-        InternalError::Error("Tux Broke Something really bad".to_string())
-    }
-}
-
 impl From<hyper::Error> for InternalError {
     fn from(err: hyper::Error) -> InternalError {
         InternalError::Error(err.to_string())
@@ -41,3 +41,14 @@ pub fn error<S: Into<String>>(value: S, status: Option<StatusCode>) -> SiteRespo
     )
     .error(status.unwrap_or(StatusCode::BAD_REQUEST))
 }
+
+pub fn too_many_requests() -> SiteResponse {
+    APIResponse::new(
+        false,
+        Some(RequestErrorResponse {
+            user_friendly_message: Some("Too many attempts. Try again later".to_string()),
+            error_code: Some("TOO_MANY_REQUESTS".to_string()),
+        }),
+    )
+    .error(StatusCode::TOO_MANY_REQUESTS)
+}
@@ -10,8 +10,11 @@ use actix_web::{post, HttpRequest};
 use serde::{Deserialize, Serialize};
 
 use crate::settings::utils::quick_add;
-use crate::user::action::add_new_user;
-use crate::user::models::{Status, User, UserPermissions, UserProperties};
+use crate::user::action::{add_new_user, get_user_by_name};
+use crate::user::role::action::{add_user_role, get_role_by_name};
+use crate::user::role::models::{UserRole, ADMIN_ROLE};
+use crate::user::title::matcher::{best_title, NO_TITLE};
+use crate::user::models::{Status, User, UserProperties};
 
 pub fn init(cfg: &mut web::ServiceConfig) {
     cfg.service(install_post).service(installed);
@@ -52,24 +55,29 @@ pub async fn install_post(
         username: form.username.clone(),
         password: crate::user::utils::hash(form.password.clone())?,
         password_changed: get_current_time(),
-        permissions: UserPermissions {
-            admin: true,
-            moderator: true,
-            submit: true,
-            review_user: true,
-            login: true,
-        },
         status: Status::Approved,
         status_changed: utils::get_current_time(),
         discoverer: "OG".to_string(),
         reviewer: "OG".to_string(),
         properties,
-        title: utils::is_valid(&form.username, &titles)
-            .unwrap_or_else(|| "No Title Identified".to_string()),
+        title: best_title(&form.username, &titles.get()).unwrap_or_else(|| NO_TITLE.to_string()),
         birthday: None,
         created: utils::get_current_time(),
+        title_manual: false,
     };
     add_new_user(&user, &conn).unwrap();
+    // The first user runs the site
+    let admin = get_role_by_name(ADMIN_ROLE, &conn)?;
+    let user = get_user_by_name(&user.username, &conn)?;
+    if let (Some(admin), Some(user)) = (admin, user) {
+        let role = UserRole {
+            id: 0,
+            user: user.id,
+            role: admin.id,
+            created: get_current_time(),
+        };
+        add_user_role(&role, &conn)?;
+    }
     quick_add("installed", "true".to_string(), &conn)?;
     APIResponse::new(true, Some(true)).respond(&r)
 }
@@ -6,50 +6,50 @@ extern crate dotenv;
 extern crate strum;
 extern crate strum_macros;
 
-use std::collections::HashMap;
 use std::fs::{OpenOptions, read_to_string};
 use std::io::Write;
-use std::ops::Sub;
-use std::path::Path;
-
-use std::sync::{Arc, Mutex};
-use std::thread::sleep;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use actix_cors::Cors;
-use std::thread;
 
 use actix_files::Files;
 
 use actix_web::web::{Data, PayloadConfig};
-use actix_web::{get, middleware, web, App, HttpRequest, HttpServer};
+use actix_web::{get, middleware, web, App, HttpRequest, HttpResponse, HttpServer};
 
-use chrono::{DateTime, Duration, Local};
 use diesel::prelude::*;
 use diesel::r2d2::{self, ConnectionManager};
-use hyper::{Body, Client, Method, Request};
-
-use hyper_tls::HttpsConnector;
-
-use log::{debug, error, info};
+use log::{error, info, warn};
 
 use crate::user::models::{BackupUser, User};
 use nitro_log::config::Config;
 use nitro_log::NitroLogger;
-use rraw::auth::{AnonymousAuthenticator, PasswordAuthenticator};
+use rraw::auth::PasswordAuthenticator;
 
 use crate::api_response::{APIResponse, SiteResponse};
 use serde::{Deserialize, Serialize};
 
+use crate::reddit::live::LiveReddit;
+use crate::reddit::oauth::RedditOAuth;
+use crate::reddit::RedditGateway;
+use crate::user::title::catalogue::{reload_loop, TitleCatalogue};
+use crate::user::purge::purge_loop;
+use crate::user::rate_limit::RateLimiter;
 use crate::user::title::Titles;
+use crate::user::utils::{hash_legacy_tokens, restore_roles};
 
-use crate::utils::{get_current_time, installed, is_valid, Resources};
+use crate::utils::{get_current_time, installed, Resources};
 
 mod admin;
 mod api_response;
+mod audit;
+mod discovery;
 mod error;
 mod frontend;
 mod install;
 mod moderator;
+mod reddit;
 pub mod schema;
 mod settings;
 pub mod user;
@@ -57,12 +57,7 @@ pub mod utils;
 
 use clap::Parser;
 use futures_util::TryFutureExt;
-use rraw::comments::CommentRetriever;
-use rraw::responses::{RedditResponse, RedditTypeResponse};
-use rraw::submission::response::SubmissionsResponse;
-use rraw::submission::{SubmissionRetriever, SubmissionType};
 use crate::user::action::{add_new_user, get_user_by_name, get_users_for_backup};
-use crate::user::utils::quick_add;
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
@@ -75,27 +70,11 @@ struct Cli {
 
 type DbPool = r2d2::Pool<ConnectionManager<MysqlConnection>>;
 pub type Database = web::Data<DbPool>;
-pub type RN = web::Data<Arc<Mutex<RNCore>>>;
-pub type RedditClient = web::Data<rraw::Client<PasswordAuthenticator>>;
-pub type TitleData = web::Data<Titles>;
-
-pub struct RNCore {
-    pub users_being_worked_on: HashMap<i64, DateTime<Local>>,
-}
-
-impl RNCore {
-    fn new() -> RNCore {
-        RNCore {
-            users_being_worked_on: HashMap::new(),
-        }
-    }
-    pub fn add_id(&mut self, id: i64) {
-        self.users_being_worked_on.insert(id, Local::now());
-    }
-    fn remove_id(&mut self, i: &i64) {
-        self.users_being_worked_on.remove(i);
-    }
-}
+pub type RedditClient = web::Data<dyn RedditGateway>;
+pub type TitleData = web::Data<TitleCatalogue>;
+pub type RateLimit = web::Data<RateLimiter>;
+/// None if the Reddit login is not set up
+pub type OAuthData = web::Data<Option<RedditOAuth>>;
 
 embed_migrations!();
 #[actix_web::main]
@@ -141,9 +120,12 @@ async fn main() -> std::io::Result<()> {
                 error!("Unable to find user. Error {}", query_e);
             } else if let Ok(user_query) = user_query {
                 if user_query.is_none() {
+                    let roles = user.roles.clone();
                     let user: User = user.into();
                     if let Err(error) = add_new_user(&user, &connection) {
                         error!("Unable to add user {}. Error {}", user.username, error);
+                    } else if let Err(error) = restore_roles(&user.username, roles, &connection) {
+                        error!("Unable to restore the roles of {} {:?}", user.username, error);
                     }
                 }
             }
@@ -152,34 +134,34 @@ async fn main() -> std::io::Result<()> {
         return Ok(());
     }
 
-    std::env::set_var("INSTALLED", "false");
-    info!("Loading Title Info From");
-    let https = HttpsConnector::new();
-    let hyper = Client::builder().build::<_, hyper::Body>(https);
-
-    let request = Request::builder()
-        .method(Method::GET)
-        .uri(std::env::var("TITLES").expect("Missing Titles Param"))
-        .body(Body::empty())
-        .unwrap();
-    let result = hyper.request(request).await;
-    if let Err(error) = result {
-        error!("Unable to Load Titles File: {}", error);
+    // The export and import do not touch auth tokens so they work without TOKEN_SECRET
+    if std::env::var("TOKEN_SECRET").is_err() {
+        println!("TOKEN_SECRET is not set. Set it in the .env file to start the site");
         return Ok(());
     }
-    let response = result.unwrap();
-    let bytes = hyper::body::to_bytes(response.into_body()).await;
-    if let Err(error) = bytes {
-        error!("Unable to Load Titles File: {}", error);
-        return Ok(());
+    match hash_legacy_tokens(&connection) {
+        Ok(hashed) if hashed > 0 => info!("Hashed {} auth tokens", hashed),
+        Ok(_) => {}
+        Err(error) => {
+            println!("Unable to hash the old auth tokens {:?}", error);
+            return Ok(());
+        }
     }
-    let string = String::from_utf8(bytes.unwrap().to_vec()).unwrap();
-    let result1: Result<Titles, serde_json::Error> = serde_json::from_str(string.as_str());
-    if let Err(error) = result1 {
-        error!("Unable to Load Titles File: {}", error);
-        return Ok(());
+
+    std::env::set_var("INSTALLED", "false");
+    info!("Loading Title Info");
+    let titles_data = Data::new(
+        TitleCatalogue::load(
+            std::env::var("TITLES").expect("Missing Titles Param"),
+            PathBuf::from(
+                std::env::var("TITLES_CACHE").unwrap_or_else(|_| "titles-cache.json".to_string()),
+            ),
+        )
+        .await,
+    );
+    if let Err(error) = titles_data.sync(&connection) {
+        error!("Unable to load the Titles from the Database {:?}", error);
     }
-    let titles_data = result1.unwrap();
 
     if !installed(&connection).unwrap() {
         info!("Initializing In Installer");
@@ -193,7 +175,7 @@ async fn main() -> std::io::Result<()> {
                 )
                 .wrap(middleware::Logger::default())
                 .app_data(Data::new(pool.clone()))
-                .app_data(Data::new(titles_data.clone()))
+                .app_data(titles_data.clone())
                 .app_data(PayloadConfig::new(1024 * 1024 * 1024))
                 .configure(frontend::init)
                 .configure(install::init)
@@ -214,63 +196,17 @@ async fn main() -> std::io::Result<()> {
     let client = rraw::Client::login(arc, "RedditNobility bot(by u/KingTuxWH)".to_string())
         .await
         .unwrap();
-    let site_core = Arc::new(Mutex::new(RNCore::new()));
-    let reference = site_core.clone();
-
-    thread::spawn(move || {
-        let site_core = reference;
-        loop {
-            info!("Starting Core Cleanup!");
-            let result = site_core.lock();
-            if result.is_err() {
-                panic!("Oh NO!.... The Site Core..... It's Broken")
-            }
-            let mut rr = result.unwrap();
-            for x in rr.users_being_worked_on.clone() {
-                let x1: Duration = Local::now().sub(x.1);
-                if x1.num_minutes() > 3 {
-                    debug!("Removing User {}", &x.0);
-                    rr.remove_id(&x.0);
-                }
-            }
-            drop(rr);
-            info!("Core Cleanup Over!");
-            sleep(Duration::minutes(5).to_std().unwrap())
-        }
-    });
+    let reddit: Arc<dyn RedditGateway> = Arc::new(LiveReddit::new(client, "RedditNobility"));
+    info!("Initializing Discovery");
+    actix_web::rt::spawn(discovery::discovery_loop(pool.clone(), titles_data.clone()));
+    actix_web::rt::spawn(reload_loop(titles_data.clone(), pool.clone()));
+    actix_web::rt::spawn(purge_loop(pool.clone()));
+    let rate_limiter = Data::new(RateLimiter::default());
+    let reddit_oauth = Data::new(RedditOAuth::from_env());
+    if reddit_oauth.is_none() {
+        warn!("Reddit Login is not configured");
+    }
     info!("Initializing Web Server");
-    let my_titles = titles_data.clone();
-    thread::spawn( || async move{
-        let my_titles = my_titles.clone();
-        loop {
-            let string = std::env::var("DATABASE_URL").expect("DATABASE_URL");
-            let result = MysqlConnection::establish(&*string).unwrap();
-            let client = rraw::Client::login(AnonymousAuthenticator::new(), "RedditNobility bot(by u/KingTuxWH)".to_string()).await.unwrap();
-            let r_all = client.subreddit("all").await.unwrap();
-            let new_list: SubmissionsResponse = r_all.get_submissions("hot", None).await.unwrap();
-            for submission_response in new_list.data.children.iter() {
-                let author = submission_response.data.author.clone();
-                if let Some(title) = crate::utils::is_valid(&author, &my_titles) {
-                    quick_add(&author, "Bot", &result, &my_titles).unwrap();
-                }
-                let submission = submission_response.data.to_submission(&client);
-                let comments = submission.get_comments(None).await.unwrap();
-                let listings = &comments.get(1).unwrap().data;
-                for comment in listings.children.iter() {
-                    if let RedditTypeResponse::Comment(comment) = &comment.data {
-                        let author = comment.author.as_ref().unwrap();
-                        if let Some(title) = crate::utils::is_valid(&author, &my_titles) {
-                            quick_add(&author, "Bot", &result, &my_titles).unwrap();
-                        }
-                    }
-                }
-                let time = Duration::seconds(60);
-                thread::sleep(time.to_std().unwrap());
-            }
-            let time = Duration::seconds(7200);
-            thread::sleep(time.to_std().unwrap());
-        }
-    });
     let server = HttpServer::new(move || {
         App::new()
             .wrap(
@@ -281,17 +217,21 @@ async fn main() -> std::io::Result<()> {
             )
             .wrap(middleware::Logger::default())
             .app_data(Data::new(pool.clone()))
-            .app_data(Data::new(site_core.clone()))
-            .app_data(Data::new(client.clone()))
-            .app_data(Data::new(titles_data.clone()))
+            .app_data(Data::from(reddit.clone()))
+            .app_data(titles_data.clone())
+            .app_data(rate_limiter.clone())
+            .app_data(reddit_oauth.clone())
             .app_data(Data::new(PayloadConfig::new(1024 * 1024 * 1024)))
             .service(titles)
+            .service(titles_export)
             .configure(error::handlers::init)
             .configure(user::init)
             .configure(moderator::init)
             .configure(frontend::init)
             .configure(settings::init)
             .configure(admin::init)
+            .configure(discovery::init)
+            .configure(audit::init)
             // TODO Make sure this is the correct way of handling vue and actix together. Also learn about packaging the website.
             .service(Files::new("/", std::env::var("SITE_DIR").unwrap()).show_files_listing())
     })
@@ -335,5 +275,14 @@ pub struct InstallRequest {
 
 #[get("/titles")]
 async fn titles(req: HttpRequest, title: TitleData) -> SiteResponse {
-    APIResponse::respond_new(Some(title), &req)
+    APIResponse::respond_new(Some(title.get().as_ref().clone()), &req)
+}
+
+/// The titles in the titles.schema.json format for the Java and TypeScript converters
+#[get("/titles.json")]
+async fn titles_export(title: TitleData) -> SiteResponse {
+    let content = serde_json::to_string_pretty(title.get().as_ref())?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(content))
 }
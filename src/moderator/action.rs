@@ -1,25 +1,96 @@
-use crate::user::models::Status;
-use diesel::dsl::count;
+use crate::discovery::BOT;
+use crate::moderator::models::{
+    DiscovererKind, QueueFilter, QueueOrder, ReviewClaim, ReviewDecision, StatusHistory,
+};
+use crate::user::models::{Status, User};
+use crate::utils::get_current_time;
+use diesel::dsl::{count, count_star, min, sql};
 use diesel::prelude::*;
+use diesel::result::Error as DieselError;
 
 use diesel::MysqlConnection;
+use std::collections::HashMap;
 
-/// Updates the User Status, Moderator Who Changed it, and the time it was changed
+/// Updates the User Status, Moderator Who Changed it, and the time it was changed.
+/// The change is added to the users status history
 pub fn update_status(
     user: &i64,
     ns: Status,
     md: &str,
     time: i64,
+    note: Option<String>,
     conn: &MysqlConnection,
 ) -> Result<(), diesel::result::Error> {
     use crate::schema::users::dsl::*;
 
-    diesel::update(users.filter(id.eq(user)))
-        .set((status.eq(&ns), status_changed.eq(&time), reviewer.eq(&md)))
+    conn.transaction(|| {
+        let (old_status, current_title): (Status, String) = users
+            .filter(id.eq(user))
+            .select((status, title))
+            .first(conn)?;
+        diesel::update(users.filter(id.eq(user)))
+            .set((status.eq(&ns), status_changed.eq(&time), reviewer.eq(&md)))
+            .execute(conn)?;
+        let history = StatusHistory {
+            id: 0,
+            user: *user,
+            old_status,
+            status: ns,
+            reviewer: md.to_string(),
+            title: current_title,
+            note,
+            created: time,
+        };
+        add_status_history(&history, conn)
+    })
+}
+
+pub fn add_status_history(
+    history: &StatusHistory,
+    conn: &MysqlConnection,
+) -> Result<(), DieselError> {
+    use crate::schema::status_history::dsl::*;
+    diesel::insert_into(status_history)
+        .values(history)
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Every status change of the user. The oldest first
+pub fn get_status_history(
+    u: &i64,
+    conn: &MysqlConnection,
+) -> Result<Vec<StatusHistory>, DieselError> {
+    use crate::schema::status_history::dsl::*;
+    status_history
+        .filter(user.eq(u))
+        .order((created.asc(), id.asc()))
+        .load::<StatusHistory>(conn)
+}
+
+pub fn add_review_decision(
+    decision: &ReviewDecision,
+    conn: &MysqlConnection,
+) -> Result<(), DieselError> {
+    use crate::schema::review_decisions::dsl::*;
+    diesel::insert_into(review_decisions)
+        .values(decision)
         .execute(conn)?;
     Ok(())
 }
 
+/// Every decision made on the user. The newest first
+pub fn get_review_decisions(
+    u: &i64,
+    conn: &MysqlConnection,
+) -> Result<Vec<ReviewDecision>, DieselError> {
+    use crate::schema::review_decisions::dsl::*;
+    review_decisions
+        .filter(user.eq(u))
+        .order((created.desc(), id.desc()))
+        .load::<ReviewDecision>(conn)
+}
+
 pub fn get_discover_count(
     user: &str,
     after: i64,
@@ -70,3 +141,294 @@ pub fn get_approve_count_total(
 
     Ok(value)
 }
+
+/// A page of the Found users waiting for review
+pub fn get_queue(
+    filter: &QueueFilter,
+    page: i64,
+    per_page: i64,
+    conn: &MysqlConnection,
+) -> Result<Vec<User>, DieselError> {
+    use crate::schema::users::dsl::*;
+    let now = get_current_time();
+    let mut query = users.filter(status.eq(Status::Found)).into_boxed();
+    if let Some(value) = &filter.title {
+        query = query.filter(title.eq(value));
+    }
+    match filter.discoverer {
+        Some(DiscovererKind::Bot) => query = query.filter(discoverer.eq(BOT)),
+        Some(DiscovererKind::Human) => query = query.filter(discoverer.ne(BOT)),
+        None => {}
+    }
+    if let Some(value) = filter.min_age {
+        query = query.filter(created.le(now - value * 1000));
+    }
+    if let Some(value) = filter.max_age {
+        query = query.filter(created.ge(now - value * 1000));
+    }
+    if filter.unclaimed {
+        use crate::schema::review_claims;
+        let claimed = review_claims::table
+            .select(review_claims::user)
+            .filter(review_claims::expiration.ge(now));
+        query = query.filter(id.ne_all(claimed));
+    }
+    query = match filter.order {
+        QueueOrder::Oldest => query.order((created.asc(), id.asc())),
+        QueueOrder::Newest => query.order((created.desc(), id.desc())),
+        QueueOrder::HumanFirst => {
+            query.order((discoverer.eq(BOT).asc(), created.asc(), id.asc()))
+        }
+        QueueOrder::RareTitle => query.order((
+            sql::<diesel::sql_types::BigInt>(
+                "(SELECT COUNT(*) FROM users AS same WHERE same.title = users.title AND same.status = 'Found')",
+            )
+            .asc(),
+            created.asc(),
+            id.asc(),
+        )),
+    };
+    query
+        .limit(per_page)
+        .offset(page * per_page)
+        .load::<User>(conn)
+}
+
+/// When each user found in the time range was found. Only users found by the discoverer if one is given
+pub fn get_discovered_between(
+    from: i64,
+    to: i64,
+    by: Option<&str>,
+    conn: &MysqlConnection,
+) -> Result<Vec<i64>, DieselError> {
+    use crate::schema::users::dsl::*;
+    let mut query = users
+        .select(created)
+        .filter(created.ge(from).and(created.lt(to)))
+        .into_boxed();
+    if let Some(value) = by {
+        query = query.filter(discoverer.eq(value));
+    }
+    query.load(conn)
+}
+
+/// When the first review decision was recorded. None if there are none yet
+fn get_first_decision(conn: &MysqlConnection) -> Result<Option<i64>, DieselError> {
+    use crate::schema::review_decisions::dsl::*;
+    review_decisions.select(min(created)).first(conn)
+}
+
+/// Who made each review decision in the time range, when and what it was.
+/// Only decisions by the reviewer if one is given.
+/// Decisions made before review decisions were recorded come from the status and reviewer kept on the user
+fn load_decisions(
+    from: i64,
+    to: i64,
+    by: Option<&str>,
+    conn: &MysqlConnection,
+) -> Result<Vec<(String, i64, Status)>, DieselError> {
+    let recorded_since = get_first_decision(conn)?.unwrap_or(i64::MAX);
+    let mut decisions = {
+        use crate::schema::review_decisions::dsl::*;
+        let mut query = review_decisions
+            .select((reviewer, created, status))
+            .filter(created.ge(from).and(created.lt(to)))
+            .into_boxed();
+        if let Some(value) = by {
+            query = query.filter(reviewer.eq(value));
+        }
+        query.load::<(String, i64, Status)>(conn)?
+    };
+    if from < recorded_since {
+        use crate::schema::users::dsl::*;
+        // Only the last decision on each user is known
+        let until = to.min(recorded_since);
+        let mut query = users
+            .select((reviewer, status_changed, status))
+            .filter(status_changed.ge(from).and(status_changed.lt(until)))
+            .filter(status.eq_any(vec![Status::Approved, Status::Denied]))
+            .filter(reviewer.ne(""))
+            .into_boxed();
+        if let Some(value) = by {
+            query = query.filter(reviewer.eq(value));
+        }
+        decisions.extend(query.load::<(String, i64, Status)>(conn)?);
+    }
+    Ok(decisions)
+}
+
+/// When each review decision in the time range was made and what it was.
+/// Only decisions by the reviewer if one is given
+pub fn get_decisions_between(
+    from: i64,
+    to: i64,
+    by: Option<&str>,
+    conn: &MysqlConnection,
+) -> Result<Vec<(i64, Status)>, DieselError> {
+    let decisions = load_decisions(from, to, by, conn)?;
+    Ok(decisions
+        .into_iter()
+        .map(|(_, time, decision)| (time, decision))
+        .collect())
+}
+
+/// How many users each person found in the time range. Users found by the bot are left out
+pub fn get_discover_counts_between(
+    from: i64,
+    to: i64,
+    conn: &MysqlConnection,
+) -> Result<Vec<(String, i64)>, DieselError> {
+    use crate::schema::users::dsl::*;
+    users
+        .filter(created.ge(from).and(created.lt(to)))
+        .filter(discoverer.ne(BOT))
+        .group_by(discoverer)
+        .select((discoverer, count_star()))
+        .load(conn)
+}
+
+/// How many review decisions each person made in the time range
+pub fn get_review_counts_between(
+    from: i64,
+    to: i64,
+    conn: &MysqlConnection,
+) -> Result<Vec<(String, i64)>, DieselError> {
+    let mut counts: HashMap<String, i64> = HashMap::new();
+    for (name, _, _) in load_decisions(from, to, None, conn)? {
+        *counts.entry(name).or_default() += 1;
+    }
+    Ok(counts.into_iter().collect())
+}
+
+/// Found users per title. The biggest first
+pub fn get_queue_count_by_title(conn: &MysqlConnection) -> Result<Vec<(String, i64)>, DieselError> {
+    use crate::schema::users::dsl::*;
+    users
+        .filter(status.eq(Status::Found))
+        .group_by(title)
+        .select((title, count_star()))
+        .order(count_star().desc())
+        .load(conn)
+}
+
+/// Found users found by the bot or by people
+pub fn get_queue_count_by_discoverer(
+    kind: &DiscovererKind,
+    conn: &MysqlConnection,
+) -> Result<i64, DieselError> {
+    use crate::schema::users::dsl::*;
+    let query = users.filter(status.eq(Status::Found)).into_boxed();
+    let query = match kind {
+        DiscovererKind::Bot => query.filter(discoverer.eq(BOT)),
+        DiscovererKind::Human => query.filter(discoverer.ne(BOT)),
+    };
+    query.select(count_star()).first(conn)
+}
+
+/// When the oldest Found user was found
+pub fn get_queue_oldest(conn: &MysqlConnection) -> Result<Option<i64>, DieselError> {
+    use crate::schema::users::dsl::*;
+    users
+        .filter(status.eq(Status::Found))
+        .select(min(created))
+        .first(conn)
+}
+
+/// Claims that have not expired on users still in the queue
+pub fn get_claimed_count(conn: &MysqlConnection) -> Result<i64, DieselError> {
+    use crate::schema::review_claims::dsl::*;
+    use crate::schema::users;
+    let found = users::table
+        .select(users::id)
+        .filter(users::status.eq(Status::Found));
+    review_claims
+        .filter(expiration.ge(get_current_time()))
+        .filter(user.eq_any(found))
+        .select(count_star())
+        .first(conn)
+}
+
+pub fn get_claim(u: &i64, conn: &MysqlConnection) -> Result<Option<ReviewClaim>, DieselError> {
+    use crate::schema::review_claims::dsl::*;
+    review_claims
+        .filter(user.eq(u).and(expiration.ge(get_current_time())))
+        .first::<ReviewClaim>(conn)
+        .optional()
+}
+
+/// Claims the reviewer is holding that have not expired. The oldest first
+pub fn get_claims_by_reviewer(
+    r: &str,
+    conn: &MysqlConnection,
+) -> Result<Vec<ReviewClaim>, DieselError> {
+    use crate::schema::review_claims::dsl::*;
+    review_claims
+        .filter(reviewer.eq(r).and(expiration.ge(get_current_time())))
+        .order((created.asc(), id.asc()))
+        .load::<ReviewClaim>(conn)
+}
+
+/// Claims the user for the reviewer. If the reviewer already holds the claim it is renewed.
+/// The unique key on `user` makes sure only one claim can win, the claim holding the user is returned
+pub fn claim_user(
+    u: &i64,
+    r: &str,
+    length: i64,
+    conn: &MysqlConnection,
+) -> Result<ReviewClaim, DieselError> {
+    use crate::schema::review_claims::dsl::*;
+    conn.transaction(|| {
+        let now = get_current_time();
+        diesel::delete(review_claims)
+            .filter(user.eq(u).and(expiration.lt(now)))
+            .execute(conn)?;
+        let claim = ReviewClaim {
+            id: 0,
+            user: *u,
+            reviewer: r.to_string(),
+            expiration: now + length,
+            created: now,
+        };
+        let inserted = diesel::insert_or_ignore_into(review_claims)
+            .values(&claim)
+            .execute(conn)?;
+        if inserted == 0 {
+            diesel::update(review_claims.filter(user.eq(u).and(reviewer.eq(r))))
+                .set(expiration.eq(now + length))
+                .execute(conn)?;
+        }
+        review_claims.filter(user.eq(u)).first::<ReviewClaim>(conn)
+    })
+}
+
+/// Extends the claim if the reviewer still holds it. Returns None if the claim was lost
+pub fn renew_claim(
+    u: &i64,
+    r: &str,
+    length: i64,
+    conn: &MysqlConnection,
+) -> Result<Option<ReviewClaim>, DieselError> {
+    use crate::schema::review_claims::dsl::*;
+    let now = get_current_time();
+    let updated = diesel::update(
+        review_claims.filter(user.eq(u).and(reviewer.eq(r)).and(expiration.ge(now))),
+    )
+    .set(expiration.eq(now + length))
+    .execute(conn)?;
+    if updated == 0 {
+        return Ok(None);
+    }
+    review_claims
+        .filter(user.eq(u))
+        .first::<ReviewClaim>(conn)
+        .optional()
+}
+
+/// Drops the claim on the user no matter who is holding it
+pub fn release_claim(u: &i64, conn: &MysqlConnection) -> Result<(), DieselError> {
+    use crate::schema::review_claims::dsl::*;
+    diesel::delete(review_claims)
+        .filter(user.eq(u))
+        .execute(conn)?;
+    Ok(())
+}
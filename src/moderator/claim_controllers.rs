@@ -0,0 +1,84 @@
+use actix_web::http::StatusCode;
+use actix_web::{post, web, HttpRequest};
+
+use crate::api_response::{APIResponse, SiteResponse};
+use crate::error::response::not_found;
+use crate::moderator::action::{claim_user, get_claim, release_claim, renew_claim};
+use crate::moderator::models::ClaimResponse;
+use crate::moderator::utils::claim_length;
+use crate::user::action::get_user_by_name;
+use crate::user::authentication::{Authenticated, RequireReviewer};
+use crate::user::role::models::Permission;
+use crate::Database;
+
+#[post("/api/moderator/review/{user}/claim")]
+pub async fn claim(
+    database: Database,
+    reviewer: Authenticated<RequireReviewer>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> SiteResponse {
+    let username = path.into_inner();
+    let conn = database.get()?;
+    let user = get_user_by_name(&username, &conn)?;
+    if user.is_none() {
+        return not_found();
+    }
+    let user = user.unwrap();
+    let claim = claim_user(&user.id, &reviewer.username, claim_length(&conn)?, &conn)?;
+    let response = ClaimResponse::new(&user.username, claim, &reviewer.username);
+    if !response.holder {
+        return APIResponse::new(false, Some(response)).error(StatusCode::CONFLICT);
+    }
+    APIResponse::new(true, Some(response)).respond(&req)
+}
+
+#[post("/api/moderator/review/{user}/heartbeat")]
+pub async fn heartbeat(
+    database: Database,
+    reviewer: Authenticated<RequireReviewer>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> SiteResponse {
+    let username = path.into_inner();
+    let conn = database.get()?;
+    let user = get_user_by_name(&username, &conn)?;
+    if user.is_none() {
+        return not_found();
+    }
+    let user = user.unwrap();
+    let claim = renew_claim(&user.id, &reviewer.username, claim_length(&conn)?, &conn)?;
+    if let Some(claim) = claim {
+        let response = ClaimResponse::new(&user.username, claim, &reviewer.username);
+        return APIResponse::new(true, Some(response)).respond(&req);
+    }
+    // The claim was lost. Tell them who has it now if anyone
+    let claim = get_claim(&user.id, &conn)?
+        .map(|claim| ClaimResponse::new(&user.username, claim, &reviewer.username));
+    APIResponse::new(false, claim).error(StatusCode::CONFLICT)
+}
+
+#[post("/api/moderator/review/{user}/release")]
+pub async fn release(
+    database: Database,
+    reviewer: Authenticated<RequireReviewer>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> SiteResponse {
+    let username = path.into_inner();
+    let conn = database.get()?;
+    let user = get_user_by_name(&username, &conn)?;
+    if user.is_none() {
+        return not_found();
+    }
+    let user = user.unwrap();
+    if let Some(claim) = get_claim(&user.id, &conn)? {
+        // claims.release lets Moderators free up a claim someone walked away from
+        if !claim.reviewer.eq(&reviewer.username) && !reviewer.has(Permission::ClaimsRelease) {
+            let response = ClaimResponse::new(&user.username, claim, &reviewer.username);
+            return APIResponse::new(false, Some(response)).error(StatusCode::CONFLICT);
+        }
+        release_claim(&user.id, &conn)?;
+    }
+    APIResponse::new(true, Some(true)).respond(&req)
+}
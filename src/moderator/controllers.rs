@@ -1,28 +1,51 @@
 use actix_web::{get, post, web, HttpRequest};
 
 use crate::api_response::{APIResponse, SiteResponse};
-use crate::{utils, Database, RedditClient, User, RN};
+use crate::audit::models::AuditAction;
+use crate::audit::utils::audit;
+use crate::{utils, Database, RedditClient, TitleData, User};
 
 use crate::error::response::{bad_request, not_found, unauthorized};
 use crate::user::action::{
-    delete_user, get_found_users, get_user_by_name, update_properties, update_title,
+    delete_auth_tokens, delete_user, get_pending_second_review, get_team_members, get_user_by_id,
+    get_user_by_name, get_usernames, update_properties, update_title,
 };
-use crate::user::utils::get_user_by_header;
+use crate::user::authentication::{
+    Authenticated, RequireReviewRemove, RequireReviewer, RequireUsersEdit, RequireUsersView,
+};
+use crate::user::role::models::Permission;
 use actix_web::http::StatusCode;
 use actix_web::web::Json;
 use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc};
-use log::{debug, error, trace};
+use log::{debug, error, trace, warn};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::str::FromStr;
-use rraw::error::Error::HTTPError;
 
 use crate::moderator::action::{
-    get_approve_count, get_approve_count_total, get_discover_count, get_discover_count_total,
+    add_review_decision, claim_user, get_approve_count, get_approve_count_total, get_claim,
+    get_claimed_count, get_claims_by_reviewer, get_decisions_between, get_discover_count,
+    get_discover_count_total, get_discover_counts_between, get_discovered_between, get_queue,
+    get_queue_count_by_discoverer, get_queue_count_by_title, get_queue_oldest,
+    get_review_counts_between, get_review_decisions, get_status_history, release_claim,
+    renew_claim,
+};
+use crate::moderator::models::{
+    Bucket, ClaimResponse, DenialReason, DiscovererKind, LeaderboardSort, QueueFilter,
+    ReviewDecision,
+};
+use crate::reddit::models::{Comment, RedditPost};
+use crate::reddit::RedditGateway;
+use crate::moderator::utils::{
+    build_leaderboard, build_series, claim_length, needs_second_review, MAX_OFFSET,
+    MAX_SERIES_TIME,
 };
 use strum::ParseError;
 
-use crate::user::models::Status;
-use crate::utils::{get_current_time, yeet};
+use crate::error::internal_error::InternalError;
+use crate::user::models::{Level, Status};
+use diesel::MysqlConnection;
+use crate::utils::get_current_time;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserStats {
@@ -32,46 +55,64 @@ pub struct UserStats {
     pub users_reviewed_this_month: i64,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModeratorUser {
+    #[serde(flatten)]
+    pub user: User,
+    //The newest first
+    pub decisions: Vec<ReviewDecision>,
+}
+
 #[get("/moderator/user/{user}")]
 pub async fn user_page(
     database: Database,
+    _moderator: Authenticated<RequireUsersView>,
     path: web::Path<String>,
     req: HttpRequest,
 ) -> SiteResponse {
     let username = path.into_inner();
     let connection = database.get()?;
-    let user = get_user_by_header(req.headers(), &connection)?;
-    if user.is_none() {
-        return unauthorized();
-    }
-    let user = user.unwrap();
-    if !user.permissions.moderator {
-        return unauthorized();
+    let lookup = get_user_by_name(&username, &connection)?;
+    if lookup.is_none() {
+        return APIResponse::<ModeratorUser>::respond_new(None, &req);
     }
+    let user = lookup.unwrap();
+    let decisions = get_review_decisions(&user.id, &connection)?;
+    APIResponse::respond_new(Some(ModeratorUser { user, decisions }), &req)
+}
+
+#[get("/moderator/user/{user}/history")]
+pub async fn user_history(
+    database: Database,
+    _moderator: Authenticated<RequireUsersView>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> SiteResponse {
+    let username = path.into_inner();
+    let connection = database.get()?;
     let lookup = get_user_by_name(&username, &connection)?;
-    APIResponse::<User>::respond_new(lookup, &req)
+    if lookup.is_none() {
+        return not_found();
+    }
+    let history = get_status_history(&lookup.unwrap().id, &connection)?;
+    APIResponse::respond_new(Some(history), &req)
 }
 
 #[get("/moderator/user/{user}/stats")]
 pub async fn user_stats(
     database: Database,
+    me: Authenticated,
     path: web::Path<String>,
     req: HttpRequest,
 ) -> SiteResponse {
     let username = path.into_inner();
     let connection = database.get()?;
-    let me = get_user_by_header(req.headers(), &connection)?;
-
-    if me.is_none() {
-        return unauthorized();
-    }
-    let me = me.unwrap();
     let lookup = get_user_by_name(&username, &connection)?;
     if lookup.is_none() {
         return not_found();
     }
     let lookup = lookup.unwrap();
-    if !me.username.eq(&lookup.username) && !me.permissions.moderator {
+    if !me.username.eq(&lookup.username) && !me.has(Permission::UsersView) {
         return unauthorized();
     }
     let lookup = get_user_by_name(&username, &connection)?.unwrap();
@@ -97,17 +138,12 @@ fn get_month_timestamp() -> i64 {
 }
 
 #[get("/moderator/stats")]
-pub async fn system_stats(database: Database, req: HttpRequest) -> SiteResponse {
+pub async fn system_stats(
+    database: Database,
+    _moderator: Authenticated<RequireUsersView>,
+    req: HttpRequest,
+) -> SiteResponse {
     let connection = database.get()?;
-    let me = get_user_by_header(req.headers(), &connection)?;
-    if me.is_none() {
-        // return unauthorized();
-    }
-    //let me = me.unwrap();
-
-    // if !me.permissions.moderator {
-    //    return unauthorized();
-    // }
     let i = get_month_timestamp();
     let users_stats = UserStats {
         users_discovered: get_discover_count_total(0, &connection)?,
@@ -119,6 +155,220 @@ pub async fn system_stats(database: Database, req: HttpRequest) -> SiteResponse
     APIResponse::<UserStats>::respond_new(Some(users_stats), &req)
 }
 
+/// A day in milliseconds
+const DAY: i64 = 24 * 60 * 60 * 1000;
+/// How many users are loaded at once when looking for the next user
+const QUEUE_PAGE: i64 = 25;
+/// How many times the queue is loaded again when every loaded user was claimed by someone else
+const QUEUE_ATTEMPTS: usize = 3;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct PageQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// A page of the review queue. Takes the queue filters and `page` and `per_page`
+#[get("/api/moderator/queue/users")]
+pub async fn queue_users(
+    database: Database,
+    _reviewer: Authenticated<RequireReviewer>,
+    req: HttpRequest,
+) -> SiteResponse {
+    let conn = database.get()?;
+    let filter: QueueFilter = match serde_qs::from_str(req.query_string()) {
+        Ok(filter) => filter,
+        Err(_) => return bad_request("Invalid Queue Filter"),
+    };
+    if !filter.valid_ages() {
+        return bad_request("Invalid Queue Age");
+    }
+    let page: PageQuery = serde_qs::from_str(req.query_string()).unwrap_or_default();
+    let per_page = page.per_page.unwrap_or(50).clamp(1, 500);
+    let page = page.page.unwrap_or(0).max(0);
+    if page.checked_mul(per_page).is_none() {
+        return bad_request("Page is too big");
+    }
+    let users = get_queue(&filter, page, per_page, &conn)?;
+    APIResponse::respond_new(Some(users), &req)
+}
+
+/// The most points a statistics series can have
+const MAX_SERIES_POINTS: i64 = 1000;
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct SeriesQuery {
+    //Milliseconds. Defaults to 30 days before to
+    pub from: Option<i64>,
+    //Milliseconds. Defaults to now
+    pub to: Option<i64>,
+    #[serde(default)]
+    pub bucket: Bucket,
+    //Only count what this user discovered and reviewed
+    pub moderator: Option<String>,
+    //Minutes east of UTC the buckets start at midnight in. Defaults to 0
+    pub offset: Option<i64>,
+}
+
+/// Discovered, reviewed, approved and denied counts per bucket for the site or a single moderator
+#[get("/moderator/stats/series")]
+pub async fn stats_series(database: Database, me: Authenticated, req: HttpRequest) -> SiteResponse {
+    let conn = database.get()?;
+    let query: SeriesQuery = match serde_qs::from_str(req.query_string()) {
+        Ok(query) => query,
+        Err(_) => return bad_request("Invalid Series Query"),
+    };
+    let is_me = query.moderator.as_ref().map(|x| x.eq(&me.username)).unwrap_or(false);
+    if !is_me && !me.has(Permission::UsersView) {
+        return unauthorized();
+    }
+    let to = query.to.unwrap_or_else(get_current_time);
+    if !(0..=MAX_SERIES_TIME).contains(&to) {
+        return bad_request("to is out of range");
+    }
+    let from = query.from.unwrap_or((to - 30 * DAY).max(0));
+    if !(0..=MAX_SERIES_TIME).contains(&from) {
+        return bad_request("from is out of range");
+    }
+    if from >= to {
+        return bad_request("from has to be before to");
+    }
+    let offset = query.offset.unwrap_or(0);
+    if !(-MAX_OFFSET..=MAX_OFFSET).contains(&offset) {
+        return bad_request("offset is out of range");
+    }
+    let length = match query.bucket {
+        Bucket::Day => DAY,
+        Bucket::Week => 7 * DAY,
+        Bucket::Month => 28 * DAY,
+    };
+    if (to - from) / length > MAX_SERIES_POINTS {
+        return bad_request("Too many buckets. Use a bigger bucket or a smaller range");
+    }
+    let by = query.moderator.as_deref();
+    let series = build_series(
+        from,
+        to,
+        &query.bucket,
+        offset * 60 * 1000,
+        get_discovered_between(from, to, by, &conn)?,
+        get_decisions_between(from, to, by, &conn)?,
+    );
+    APIResponse::respond_new(Some(series), &req)
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct LeaderboardQuery {
+    //Milliseconds. Defaults to the start of the month
+    pub from: Option<i64>,
+    //Milliseconds. Defaults to now
+    pub to: Option<i64>,
+    pub level: Option<Level>,
+    #[serde(default)]
+    pub sort: LeaderboardSort,
+    pub limit: Option<usize>,
+}
+
+/// Ranks users by how many users they discovered and reviewed in the time range
+#[get("/moderator/leaderboard")]
+pub async fn leaderboard(
+    database: Database,
+    _moderator: Authenticated<RequireUsersView>,
+    req: HttpRequest,
+) -> SiteResponse {
+    let conn = database.get()?;
+    let query: LeaderboardQuery = match serde_qs::from_str(req.query_string()) {
+        Ok(query) => query,
+        Err(_) => return bad_request("Invalid Leaderboard Query"),
+    };
+    let to = query.to.unwrap_or_else(get_current_time);
+    let from = query.from.unwrap_or_else(get_month_timestamp);
+    if from >= to {
+        return bad_request("from has to be before to");
+    }
+    let members = get_team_members(&conn)?;
+    let ids: Vec<i64> = members.iter().map(|x| x.user).collect();
+    let usernames: HashMap<i64, String> = get_usernames(&ids, &conn)?.into_iter().collect();
+    let team: HashMap<String, Level> = members
+        .into_iter()
+        .filter_map(|x| usernames.get(&x.user).map(|name| (name.clone(), x.level)))
+        .collect();
+    let mut entries = build_leaderboard(
+        get_discover_counts_between(from, to, &conn)?,
+        get_review_counts_between(from, to, &conn)?,
+        team,
+        query.level.as_ref(),
+        &query.sort,
+    );
+    entries.truncate(query.limit.unwrap_or(50).clamp(1, 500));
+    APIResponse::respond_new(Some(entries), &req)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApprovalRate {
+    pub approved: i64,
+    pub denied: i64,
+    //Approved out of every decision. 0 if there were no decisions
+    pub rate: f64,
+}
+
+impl ApprovalRate {
+    fn since(after: i64, conn: &MysqlConnection) -> Result<ApprovalRate, InternalError> {
+        let decisions = get_decisions_between(after, i64::MAX, None, conn)?;
+        let approved = decisions.iter().filter(|(_, x)| x.eq(&Status::Approved)).count() as i64;
+        let denied = decisions.iter().filter(|(_, x)| x.eq(&Status::Denied)).count() as i64;
+        let total = approved + denied;
+        Ok(ApprovalRate {
+            approved,
+            denied,
+            rate: if total == 0 { 0.0 } else { approved as f64 / total as f64 },
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiscovererCounts {
+    pub bot: i64,
+    pub human: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueueStats {
+    pub total: i64,
+    pub by_title: HashMap<String, i64>,
+    pub by_discoverer: DiscovererCounts,
+    //Milliseconds since the oldest user in the queue was found
+    pub oldest_age: Option<i64>,
+    pub claimed: i64,
+    pub approval_rate_7_days: ApprovalRate,
+    pub approval_rate_30_days: ApprovalRate,
+}
+
+/// How big the review backlog is
+#[get("/api/moderator/queue")]
+pub async fn queue_stats(
+    database: Database,
+    _reviewer: Authenticated<RequireReviewer>,
+    req: HttpRequest,
+) -> SiteResponse {
+    let conn = database.get()?;
+    let now = get_current_time();
+    let by_title: HashMap<String, i64> = get_queue_count_by_title(&conn)?.into_iter().collect();
+    let stats = QueueStats {
+        total: by_title.values().sum(),
+        by_title,
+        by_discoverer: DiscovererCounts {
+            bot: get_queue_count_by_discoverer(&DiscovererKind::Bot, &conn)?,
+            human: get_queue_count_by_discoverer(&DiscovererKind::Human, &conn)?,
+        },
+        oldest_age: get_queue_oldest(&conn)?.map(|oldest| now - oldest),
+        claimed: get_claimed_count(&conn)?,
+        approval_rate_7_days: ApprovalRate::since(now - 7 * DAY, &conn)?,
+        approval_rate_30_days: ApprovalRate::since(now - 30 * DAY, &conn)?,
+    };
+    APIResponse::respond_new(Some(stats), &req)
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RedditUser {
     pub name: String,
@@ -129,67 +379,113 @@ pub struct RedditUser {
     pub top_posts: Vec<RedditPost>,
     pub top_comments: Vec<Comment>,
     pub user: User,
+    //Who is reviewing the user right now
+    pub claim: Option<ClaimResponse>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct RedditPost {
-    pub subreddit: String,
-    pub url: String,
-    pub id: String,
-    pub title: String,
-    pub content: RedditContent,
-    pub score: i64,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Comment {
-    pub subreddit: String,
-    pub url: String,
-    pub id: String,
-    pub og_post_title: String,
-    pub content: String,
-    pub score: i64,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct RedditContent {
-    pub content: Option<String>,
-    pub url: Option<String>,
-    pub over_18: bool,
+/// Adds the Reddit about data and latest activity to the user. None if Reddit does not know them
+async fn load_reddit_user(
+    user: User,
+    claim: Option<ClaimResponse>,
+    client: &dyn RedditGateway,
+) -> Result<Option<RedditUser>, InternalError> {
+    let profile = match client.profile(&user.username).await? {
+        Some(profile) => profile,
+        None => return Ok(None),
+    };
+    Ok(Some(RedditUser {
+        name: profile.account.name,
+        avatar: profile.account.icon_img,
+        comment_karma: profile.account.comment_karma,
+        total_karma: profile.account.total_karma,
+        created: profile.account.created,
+        top_posts: profile.submissions,
+        top_comments: profile.comments,
+        user,
+        claim,
+    }))
 }
 
+/// Looks up a user for review. `next` claims the first unclaimed user in the queue so two Moderators never get the same one.
+/// The queue can be filtered and ordered with the same query as the queue endpoint.
+/// Any other user has to be claimed through the claim endpoint
 #[get("/api/moderator/review/{user}")]
 pub async fn review_user(
     database: Database,
+    reviewer: Authenticated<RequireReviewer>,
     path: web::Path<String>,
     req: HttpRequest,
-    rr: RN,
     client: RedditClient,
 ) -> SiteResponse {
     let username = path.into_inner();
     let conn = database.get()?;
-    let user = get_user_by_header(req.headers(), &conn)?;
-    if user.is_none() {
-        return unauthorized();
-    }
-    let user = user.unwrap();
-    if !user.permissions.review_user {
-        return unauthorized();
-    }
-    let mut rn = rr.lock()?;
     let user = if username.eq("next") {
         trace!("Looking for Next User");
-        let mut result = get_found_users(&conn)?;
-        result.sort_by_key(|x| x.created);
+        let mut filter: QueueFilter = match serde_qs::from_str(req.query_string()) {
+            Ok(filter) => filter,
+            Err(_) => return bad_request("Invalid Queue Filter"),
+        };
+        if !filter.valid_ages() {
+            return bad_request("Invalid Queue Age");
+        }
+        filter.unclaimed = true;
+        let length = claim_length(&conn)?;
+        let can_approve = reviewer.has(Permission::ReviewApprove);
+        // A user the reviewer is still holding comes back before anything new is claimed
         let mut v = None;
-        for i in 0..result.len() {
-            let user = result.remove(i);
-            if !rn.users_being_worked_on.contains_key(&user.id) {
-                trace!("User Found {}", &user.username);
+        for claim in get_claims_by_reviewer(&reviewer.username, &conn)? {
+            let user = match get_user_by_id(&claim.user, &conn)? {
+                Some(user) => user,
+                None => continue,
+            };
+            let reviewable = match user.status {
+                Status::Found => true,
+                Status::PendingSecondReview => can_approve,
+                _ => false,
+            };
+            if reviewable && renew_claim(&user.id, &reviewer.username, length, &conn)?.is_some() {
                 v = Some(user);
                 break;
             }
         }
+        // Approvals waiting on a second Moderator go first. Only to reviewers that can confirm them
+        let pending = if can_approve {
+            get_pending_second_review(&reviewer.username, &conn)?
+        } else {
+            Vec::new()
+        };
+        for user in pending {
+            if v.is_some() {
+                break;
+            }
+            if get_claim(&user.id, &conn)?.is_some() {
+                continue;
+            }
+            let claim = claim_user(&user.id, &reviewer.username, length, &conn)?;
+            if claim.reviewer.eq(&reviewer.username) {
+                v = Some(user);
+                break;
+            }
+        }
+        // Someone else can claim a user between loading the queue and claiming it.
+        // Claimed users drop out of the queue so it is loaded again
+        for _ in 0..QUEUE_ATTEMPTS {
+            if v.is_some() {
+                break;
+            }
+            let result = get_queue(&filter, 0, QUEUE_PAGE, &conn)?;
+            if result.is_empty() {
+                break;
+            }
+            for user in result {
+                let claim = claim_user(&user.id, &reviewer.username, length, &conn)?;
+                if claim.reviewer.eq(&reviewer.username) {
+                    trace!("User Found {}", &user.username);
+                    v = Some(user);
+                    break;
+                }
+            }
+        }
         if v.is_none() {
             trace!("Unable to find User {}", &username);
             return not_found();
@@ -202,86 +498,21 @@ pub async fn review_user(
         }
         user.unwrap()
     };
-    rn.add_id(user.id);
+    let claim = get_claim(&user.id, &conn)?
+        .map(|claim| ClaimResponse::new(&user.username, claim, &reviewer.username));
 
     trace!("Grabbing About Data for {}", &user.username);
-    let r_user = client.user(user.username.clone()).await;
-    if let Err(error) = r_user {
-        error!(
-            "Failed to grab about data for {} error {}",
-            &user.username, &error
-        );
-        if let rraw::error::Error::HTTPError(error) = error {
-            return if error.eq(&rraw::error::http_error::HTTPError::NotFound) {
-                delete_user(&user.id, &conn)?;
-                bad_request("We have fixed the issue please try again")
-            } else {
-                Err(error.into())
-            };
-        } else {
-            return Err(error.into());
-        }
-    }
-    let r_user = r_user.unwrap();
-    let mut user_posts = Vec::<RedditPost>::new();
-    let mut user_comments = Vec::<Comment>::new();
-    if !r_user.user.is_suspended {
-        let submissions = r_user.submissions(None).await?;
-        let comments = r_user.comments(None).await?;
-        yeet(rn);
-
-        for x in comments.data.children {
-            let x = x.data;
-            let post = Comment {
-                subreddit: x.subreddit,
-                url: format!("https://reddit.com{}", x.permalink),
-                id: x.id.clone(),
-                og_post_title: x.link_title.unwrap().clone(),
-                content: x.body,
-                score: x.score as i64,
-            };
-            user_comments.push(post);
-        }
-        for x in submissions.data.children {
-            let x = x.data;
-            let text = x.selftext;
-            let content = if text.is_empty() {
-                RedditContent {
-                    content: None,
-                    url: x.url,
-                    over_18: x.over_18,
-                }
-            } else {
-                RedditContent {
-                    content: Some(text),
-                    url: None,
-                    over_18: x.over_18,
-                }
-            };
-            let post = RedditPost {
-                subreddit: x.subreddit,
-                url: format!("https://reddit.com{}", x.permalink),
-                id: x.id.clone(),
-                title: x.title.clone(),
-                content,
-                score: x.score as i64,
-            };
-            user_posts.push(post);
-        }
+    let (id, name) = (user.id, user.username.clone());
+    let user = load_reddit_user(user, claim, &client).await?;
+    if user.is_none() {
+        error!("Failed to grab about data for {}. Reddit does not know them", &name);
+        delete_user(&id, &conn)?;
+        release_claim(&id, &conn)?;
+        return bad_request("We have fixed the issue please try again");
     }
-    let user = RedditUser {
-        name: r_user.user.name,
-        avatar: r_user.user.icon_img,
-        comment_karma: r_user.user.comment_karma,
-        total_karma: r_user.user.total_karma,
-        created: r_user.user.created as i64,
-        top_posts: user_posts,
-        top_comments: user_comments,
-        user,
-    };
     let response = APIResponse::<RedditUser> {
         success: true,
-        data: Some(user),
+        data: user,
         status_code: Some(200),
     };
     response.respond(&req)
@@ -290,26 +521,23 @@ pub async fn review_user(
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ApproveRequest {
     pub title: Option<String>,
+    //Saved in the status history and with the decision
+    pub note: Option<String>,
+    //Why the user was denied
+    pub reason: Option<DenialReason>,
 }
 
 #[post("/api/moderator/review/{username}/{status}")]
 pub async fn review_user_update(
     database: Database,
+    reviewer: Authenticated<RequireReviewer>,
     value: web::Path<(String, String)>,
     req: HttpRequest,
     reddit_client: RedditClient,
+    titles: TitleData,
 ) -> SiteResponse {
     let (username, status) = value.into_inner();
     let conn = database.get()?;
-    let user = get_user_by_header(req.headers(), &conn)?;
-    if user.is_none() {
-        return unauthorized();
-    }
-
-    let reviewer = user.unwrap();
-    if !reviewer.permissions.review_user {
-        return unauthorized();
-    }
     trace!("Setting the User: {} Status {}", &username, &status);
 
     let option = get_user_by_name(&username, &conn)?;
@@ -318,12 +546,49 @@ pub async fn review_user_update(
     }
 
     let str: Result<Status, ParseError> = Status::from_str(status.as_str());
-    if str.is_err() {
+    if str.is_err()
+        || str.as_ref().unwrap().is_removal()
+        || str.as_ref().unwrap() == &Status::PendingSecondReview
+    {
         return bad_request("Approved or Denied".to_string());
     }
+    let permission = if str.as_ref().unwrap() == &Status::Approved {
+        Permission::ReviewApprove
+    } else {
+        Permission::ReviewDeny
+    };
+    if !reviewer.has(permission) {
+        return unauthorized();
+    }
     let user2 = option.unwrap();
+    if let Some(claim) = get_claim(&user2.id, &conn)? {
+        if !claim.reviewer.eq(&reviewer.username) {
+            return crate::error::response::error(
+                format!("{} is being reviewed by {}", &user2.username, &claim.reviewer),
+                Some(StatusCode::CONFLICT),
+            );
+        }
+    }
 
-    let status = str.unwrap();
+    let mut status = str.unwrap();
+    let x: ApproveRequest = match serde_qs::from_str(req.query_string()) {
+        Ok(x) => x,
+        Err(_) => return bad_request("Invalid Review Request"),
+    };
+    if x.reason.is_some() && status != Status::Denied {
+        return bad_request("A reason can only be given when denying");
+    }
+    if status == Status::Approved && user2.status == Status::PendingSecondReview {
+        if user2.reviewer.eq(&reviewer.username) {
+            return crate::error::response::error(
+                "A different Moderator has to confirm the approval",
+                Some(StatusCode::CONFLICT),
+            );
+        }
+    } else if status == Status::Approved && needs_second_review(&user2, &titles.get(), &conn)? {
+        debug!("{} needs a second review", &user2.username);
+        status = Status::PendingSecondReview;
+    }
     if status == Status::Approved {
         trace!("Attempting to Approve User {} on Reddit", &user2.username);
 
@@ -336,17 +601,122 @@ pub async fn review_user_update(
             );
         }
     }
-    let _properties = user2.properties;
-    let x: ApproveRequest = serde_qs::from_str(req.query_string()).unwrap();
     if let Some(title) = x.title {
         debug!("Changing {} title to {}", &user2.username, &title);
         update_title(&user2.id, &title, &conn)?;
+        audit(
+            &reviewer.username,
+            &user2.username,
+            AuditAction::TitleChange,
+            Some(user2.title.clone()),
+            Some(title),
+            &conn,
+        )?;
     }
+    let decision = ReviewDecision {
+        id: 0,
+        user: user2.id,
+        reviewer: reviewer.username.clone(),
+        status: status.clone(),
+        reason: x.reason,
+        note: x.note.clone(),
+        created: get_current_time(),
+    };
+    add_review_decision(&decision, &conn)?;
+    let pending = status == Status::PendingSecondReview;
+    let new_status = status.to_string();
     crate::moderator::action::update_status(
         &user2.id,
         status,
         &reviewer.username,
         get_current_time(),
+        x.note,
+        &conn,
+    )?;
+    audit(
+        &reviewer.username,
+        &user2.username,
+        AuditAction::StatusChange,
+        Some(user2.status.to_string()),
+        Some(new_status),
+        &conn,
+    )?;
+    release_claim(&user2.id, &conn)?;
+    if pending {
+        return APIResponse {
+            success: true,
+            data: Some(Status::PendingSecondReview),
+            status_code: Some(202),
+        }
+        .respond(&req);
+    }
+    APIResponse::new(true, Some(true)).respond(&req)
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct RemoveRequest {
+    //Saved in the status history
+    pub note: Option<String>,
+}
+
+/// Bans or Removes the user. They are removed as a contributor and logged out everywhere
+#[post("/api/moderator/remove/{username}/{status}")]
+pub async fn remove_user(
+    database: Database,
+    moderator: Authenticated<RequireReviewRemove>,
+    value: web::Path<(String, String)>,
+    req: HttpRequest,
+    reddit_client: RedditClient,
+) -> SiteResponse {
+    let (username, status) = value.into_inner();
+    let conn = database.get()?;
+
+    let str: Result<Status, ParseError> = Status::from_str(status.as_str());
+    if str.is_err() || !str.as_ref().unwrap().is_removal() {
+        return bad_request("Banned or Removed");
+    }
+    let status = str.unwrap();
+    let option = get_user_by_name(&username, &conn)?;
+    if option.is_none() {
+        return not_found();
+    }
+    let target = option.unwrap();
+    if target.id == moderator.id {
+        return bad_request("You can not remove yourself");
+    }
+    if target.status == Status::Approved {
+        trace!("Attempting to Remove User {} on Reddit", &target.username);
+        match reddit_client.remove_contributor(&target.username).await {
+            Ok(true) => {}
+            Ok(false) => warn!("{} was not a contributor on Reddit", &target.username),
+            Err(error) => {
+                error!("Unable to remove User {:?}", error);
+                return crate::error::response::error(
+                    "Unable to Process Remove Request Currently",
+                    Some(StatusCode::INTERNAL_SERVER_ERROR),
+                );
+            }
+        }
+    }
+    let x: RemoveRequest = serde_qs::from_str(req.query_string()).unwrap_or_default();
+    let new_status = status.to_string();
+    crate::moderator::action::update_status(
+        &target.id,
+        status,
+        &moderator.username,
+        get_current_time(),
+        x.note,
+        &conn,
+    )?;
+    let revoked = delete_auth_tokens(&target.id, &conn)?;
+    debug!("Revoked {} tokens of {}", revoked, &target.username);
+    release_claim(&target.id, &conn)?;
+    audit(
+        &moderator.username,
+        &target.username,
+        AuditAction::StatusChange,
+        Some(target.status.to_string()),
+        Some(new_status),
         &conn,
     )?;
     APIResponse::new(true, Some(true)).respond(&req)
@@ -360,6 +730,7 @@ pub struct ChangeRequest {
 #[post("/api/moderator/update/{user}/{key}")]
 pub async fn moderator_update_properties(
     database: Database,
+    modetator: Authenticated<RequireUsersEdit>,
     request: Json<ChangeRequest>,
     path: web::Path<(String, String)>,
     r: HttpRequest,
@@ -367,20 +738,13 @@ pub async fn moderator_update_properties(
     let (username, key) = path.into_inner();
 
     let conn = database.get()?;
-    let option = get_user_by_header(r.headers(), &conn)?;
-    if option.is_none() {
-        return unauthorized();
-    }
-    let modetator = option.unwrap();
-    if !modetator.permissions.moderator {
-        return unauthorized();
-    }
     // Update User
     let option = get_user_by_name(&username, &conn)?;
     if option.is_none() {
         return not_found();
     }
     let mut user = option.unwrap();
+    let old = serde_json::to_string(&user.properties)?;
     let value = request.0.value;
     match key.as_str() {
         "avatar" => {
@@ -393,6 +757,62 @@ pub async fn moderator_update_properties(
             return bad_request("You can only change your Avatar or Description");
         }
     }
+    let new = serde_json::to_string(&user.properties)?;
     update_properties(&user.id, user.properties, &conn)?;
+    audit(
+        &modetator.username,
+        &user.username,
+        AuditAction::PropertyChange,
+        Some(old),
+        Some(new),
+        &conn,
+    )?;
     APIResponse::new(true, Some(true)).respond(&r)
 }
+
+#[actix_web::test]
+async fn load_reddit_user_test() {
+    use crate::reddit::fake::{found_user, FakeReddit};
+    use crate::reddit::models::RedditContent;
+
+    let reddit = FakeReddit::default();
+    reddit.add_account("KingTuxWH");
+    let post = RedditPost {
+        subreddit: "RedditNobility".to_string(),
+        url: "https://reddit.com/r/RedditNobility/1".to_string(),
+        id: "1".to_string(),
+        title: "Hello".to_string(),
+        content: RedditContent {
+            content: Some("Hello".to_string()),
+            url: None,
+            over_18: false,
+        },
+        score: 1,
+    };
+    reddit
+        .state
+        .lock()
+        .unwrap()
+        .submissions
+        .insert("kingtuxwh".to_string(), vec![post]);
+    let user = found_user("KingTuxWH");
+    let loaded = load_reddit_user(user.clone(), None, &reddit).await.unwrap().unwrap();
+    assert_eq!(loaded.avatar, "https://example.com/KingTuxWH.png");
+    assert_eq!(loaded.top_posts.len(), 1);
+    assert_eq!(loaded.user.username, "KingTuxWH");
+
+    // Nothing is shown for suspended accounts
+    reddit
+        .state
+        .lock()
+        .unwrap()
+        .accounts
+        .get_mut("kingtuxwh")
+        .unwrap()
+        .is_suspended = true;
+    let loaded = load_reddit_user(user, None, &reddit).await.unwrap().unwrap();
+    assert!(loaded.top_posts.is_empty());
+
+    let missing = found_user("QueenTux");
+    assert!(load_reddit_user(missing, None, &reddit).await.unwrap().is_none());
+}
@@ -1,13 +1,26 @@
 pub mod action;
+mod claim_controllers;
 mod controllers;
+pub mod models;
+pub mod utils;
 
 use actix_web::web;
 
 pub fn init(cfg: &mut web::ServiceConfig) {
+    // Claim routes have to come before review_user_update or they will be read as a status
+    cfg.service(claim_controllers::claim)
+        .service(claim_controllers::heartbeat)
+        .service(claim_controllers::release);
     cfg.service(controllers::review_user_update)
         .service(controllers::review_user)
         .service(controllers::user_page)
         .service(controllers::user_stats)
+        .service(controllers::user_history)
         .service(controllers::system_stats)
-        .service(controllers::moderator_update_properties);
+        .service(controllers::stats_series)
+        .service(controllers::leaderboard)
+        .service(controllers::moderator_update_properties)
+        .service(controllers::remove_user)
+        .service(controllers::queue_users)
+        .service(controllers::queue_stats);
 }
@@ -0,0 +1,216 @@
+use crate::schema::*;
+use crate::user::models::{Level, Status};
+use diesel::backend::Backend;
+use diesel::deserialize::FromSql;
+use diesel::mysql::Mysql;
+use diesel::serialize::{Output, ToSql};
+use diesel::sql_types::Text;
+use diesel::{deserialize, serialize};
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::str::FromStr;
+use strum_macros::{Display, EnumString};
+
+/// A moderator's hold on a user in the review queue. Only one claim can exist per user
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "review_claims"]
+pub struct ReviewClaim {
+    pub id: i64,
+    pub user: i64,
+    //The username of the Moderator holding the claim
+    pub reviewer: String,
+    //When the claim stops being valid
+    pub expiration: i64,
+    pub created: i64,
+}
+
+/// A single change of a users status
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "status_history"]
+pub struct StatusHistory {
+    pub id: i64,
+    pub user: i64,
+    pub old_status: Status,
+    pub status: Status,
+    //The Moderator who changed the status
+    pub reviewer: String,
+    //The title the user had when the status was changed
+    pub title: String,
+    pub note: Option<String>,
+    pub created: i64,
+}
+
+/// The decision a reviewer made on a user with their reasoning
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "review_decisions"]
+pub struct ReviewDecision {
+    pub id: i64,
+    pub user: i64,
+    pub reviewer: String,
+    pub status: Status,
+    //Only set if the user was denied
+    pub reason: Option<DenialReason>,
+    pub note: Option<String>,
+    pub created: i64,
+}
+
+#[derive(
+AsExpression, Debug, Deserialize, Serialize, FromSqlRow, Clone, Display, PartialEq, EnumString,
+)]
+#[sql_type = "Text"]
+pub enum DenialReason {
+    NoTitle,
+    InappropriateName,
+    InactiveAccount,
+    Suspended,
+    Other,
+}
+
+impl ToSql<Text, Mysql> for DenialReason {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Mysql>) -> serialize::Result {
+        let s = self.to_string();
+        <String as ToSql<Text, Mysql>>::to_sql(&s, out)
+    }
+}
+
+impl FromSql<Text, Mysql> for DenialReason {
+    fn from_sql(
+        bytes: Option<&<diesel::mysql::Mysql as Backend>::RawValue>,
+    ) -> deserialize::Result<DenialReason> {
+        let t = <String as FromSql<Text, Mysql>>::from_sql(bytes)?;
+        let result: Result<DenialReason, strum::ParseError> = DenialReason::from_str(t.as_str());
+        if let Err(error) = result {
+            error!("Unable to Parse DenialReason {} Value {}", error, t);
+            return Err(Box::new(error));
+        }
+        Ok(result.unwrap())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimResponse {
+    pub username: String,
+    //Who is currently holding the claim
+    pub reviewer: String,
+    pub expiration: i64,
+    //If the requesting Moderator is the one holding the claim
+    pub holder: bool,
+}
+
+impl ClaimResponse {
+    pub fn new(username: &str, claim: ReviewClaim, me: &str) -> ClaimResponse {
+        ClaimResponse {
+            username: username.to_string(),
+            holder: claim.reviewer.eq(me),
+            reviewer: claim.reviewer,
+            expiration: claim.expiration,
+        }
+    }
+}
+
+/// Who found the users in the queue
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscovererKind {
+    Bot,
+    Human,
+}
+
+/// The order the review queue is in
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueOrder {
+    Oldest,
+    Newest,
+    //Users submitted by people before users found by the bot
+    HumanFirst,
+    //Users with a title few other users in the queue have
+    RareTitle,
+}
+
+impl Default for QueueOrder {
+    fn default() -> Self {
+        QueueOrder::Oldest
+    }
+}
+
+/// Filters and ordering for the review queue. Ages are in seconds
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct QueueFilter {
+    pub title: Option<String>,
+    pub discoverer: Option<DiscovererKind>,
+    //Only users found at least this long ago
+    pub min_age: Option<i64>,
+    //Only users found at most this long ago
+    pub max_age: Option<i64>,
+    #[serde(default)]
+    pub order: QueueOrder,
+    //Leave out users someone is reviewing
+    #[serde(default)]
+    pub unclaimed: bool,
+}
+
+impl QueueFilter {
+    /// False if an age is negative or too big to be turned into milliseconds
+    pub fn valid_ages(&self) -> bool {
+        [self.min_age, self.max_age]
+            .iter()
+            .flatten()
+            .all(|age| *age >= 0 && age.checked_mul(1000).is_some())
+    }
+}
+
+/// How long each point of a statistics series covers. Buckets are in UTC unless an offset is given and weeks start on Monday
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Bucket {
+    Day,
+    Week,
+    Month,
+}
+
+impl Default for Bucket {
+    fn default() -> Self {
+        Bucket::Day
+    }
+}
+
+/// The activity inside a single bucket
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct SeriesPoint {
+    //When the bucket starts
+    pub start: i64,
+    pub discovered: i64,
+    //Every review decision including the ones waiting for a second review
+    pub reviewed: i64,
+    pub approved: i64,
+    pub denied: i64,
+}
+
+/// What the leaderboard is ranked by
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LeaderboardSort {
+    Discovered,
+    Reviewed,
+    //Discovered and Reviewed added together
+    Total,
+}
+
+impl Default for LeaderboardSort {
+    fn default() -> Self {
+        LeaderboardSort::Total
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    //Starts at 1
+    pub rank: usize,
+    pub username: String,
+    //Only set for team members
+    pub level: Option<Level>,
+    pub discovered: i64,
+    pub reviewed: i64,
+}
@@ -0,0 +1,210 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+use diesel::MysqlConnection;
+
+use crate::error::internal_error::InternalError;
+use crate::moderator::models::{Bucket, LeaderboardEntry, LeaderboardSort, SeriesPoint};
+use crate::settings::utils::get_setting_value;
+use crate::user::models::{Level, Status, User};
+use crate::user::title::matcher::match_titles;
+use crate::Titles;
+
+/// How long a review claim lasts in milliseconds
+pub fn claim_length(conn: &MysqlConnection) -> Result<i64, InternalError> {
+    let seconds: i64 = get_setting_value("review.claim_length", conn)?;
+    Ok(seconds * 1000)
+}
+
+/// If approving the user has to be confirmed by a second Moderator.
+/// Each rule is turned on with its own `review.second_review` setting
+pub fn needs_second_review(
+    user: &User,
+    titles: &Titles,
+    conn: &MysqlConnection,
+) -> Result<bool, InternalError> {
+    let reversal = matches!(
+        user.status,
+        Status::Denied | Status::Banned | Status::Removed
+    );
+    if reversal && get_setting_value::<bool>("review.second_review.reversal", conn)? {
+        return Ok(true);
+    }
+    if get_setting_value::<bool>("review.second_review.weak_title", conn)? {
+        // A title is weak if it is not its own word inside the username
+        let weak = match_titles(&user.username, titles)
+            .first()
+            .map(|title| title.confidence < 1.0)
+            .unwrap_or(true);
+        return Ok(weak);
+    }
+    Ok(false)
+}
+
+/// The latest time a statistics series can cover. The end of 9999-12-31 UTC
+pub const MAX_SERIES_TIME: i64 = 253402300799999;
+/// The biggest time zone offset in minutes
+pub const MAX_OFFSET: i64 = 14 * 60;
+
+/// The start of the bucket the time is in. Buckets start at midnight offset milliseconds east of UTC
+pub fn bucket_start(time: i64, bucket: &Bucket, offset: i64) -> i64 {
+    let local = time + offset;
+    let date = NaiveDateTime::from_timestamp(local.div_euclid(1000), 0).date();
+    let date = match bucket {
+        Bucket::Day => date,
+        Bucket::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+        Bucket::Month => NaiveDate::from_ymd(date.year(), date.month(), 1),
+    };
+    date.and_hms(0, 0, 0).timestamp_millis() - offset
+}
+
+/// The start of the bucket after the one starting at start
+pub fn next_bucket(start: i64, bucket: &Bucket, offset: i64) -> i64 {
+    let local = start + offset;
+    let date = NaiveDateTime::from_timestamp(local.div_euclid(1000), 0).date();
+    let date = match bucket {
+        Bucket::Day => date + Duration::days(1),
+        Bucket::Week => date + Duration::days(7),
+        Bucket::Month if date.month() == 12 => NaiveDate::from_ymd(date.year() + 1, 1, 1),
+        Bucket::Month => NaiveDate::from_ymd(date.year(), date.month() + 1, 1),
+    };
+    date.and_hms(0, 0, 0).timestamp_millis() - offset
+}
+
+/// Counts the discoveries and decisions per bucket. Every bucket between from and to is included even if it is empty.
+/// from and to have to be between 0 and MAX_SERIES_TIME
+pub fn build_series(
+    from: i64,
+    to: i64,
+    bucket: &Bucket,
+    offset: i64,
+    discovered: Vec<i64>,
+    decisions: Vec<(i64, Status)>,
+) -> Vec<SeriesPoint> {
+    let mut series = BTreeMap::new();
+    let mut start = bucket_start(from, bucket, offset);
+    while start < to {
+        series.insert(
+            start,
+            SeriesPoint {
+                start,
+                ..SeriesPoint::default()
+            },
+        );
+        start = next_bucket(start, bucket, offset);
+    }
+    for time in discovered {
+        if let Some(point) = series.get_mut(&bucket_start(time, bucket, offset)) {
+            point.discovered += 1;
+        }
+    }
+    for (time, status) in decisions {
+        if let Some(point) = series.get_mut(&bucket_start(time, bucket, offset)) {
+            point.reviewed += 1;
+            match status {
+                Status::Approved => point.approved += 1,
+                Status::Denied => point.denied += 1,
+                _ => {}
+            }
+        }
+    }
+    series.into_values().collect()
+}
+
+/// Ranks everyone with discoveries or reviews. With a level only the team members of that level are ranked,
+/// including the ones without any activity
+pub fn build_leaderboard(
+    discovered: Vec<(String, i64)>,
+    reviewed: Vec<(String, i64)>,
+    team: HashMap<String, Level>,
+    level: Option<&Level>,
+    sort: &LeaderboardSort,
+) -> Vec<LeaderboardEntry> {
+    fn entry<'a>(
+        entries: &'a mut HashMap<String, LeaderboardEntry>,
+        team: &HashMap<String, Level>,
+        username: &str,
+    ) -> &'a mut LeaderboardEntry {
+        entries
+            .entry(username.to_string())
+            .or_insert_with(|| LeaderboardEntry {
+                rank: 0,
+                username: username.to_string(),
+                level: team.get(username).cloned(),
+                discovered: 0,
+                reviewed: 0,
+            })
+    }
+    let mut entries = HashMap::new();
+    for (username, count) in discovered {
+        entry(&mut entries, &team, &username).discovered += count;
+    }
+    for (username, count) in reviewed {
+        entry(&mut entries, &team, &username).reviewed += count;
+    }
+    if let Some(level) = level {
+        for (username, member) in team.iter() {
+            if member == level {
+                entry(&mut entries, &team, username);
+            }
+        }
+    }
+    let mut entries: Vec<LeaderboardEntry> = entries
+        .into_values()
+        .filter(|x| level.is_none() || x.level.as_ref() == level)
+        .collect();
+    let score = |x: &LeaderboardEntry| match sort {
+        LeaderboardSort::Discovered => x.discovered,
+        LeaderboardSort::Reviewed => x.reviewed,
+        LeaderboardSort::Total => x.discovered + x.reviewed,
+    };
+    entries.sort_by(|a, b| score(b).cmp(&score(a)).then(a.username.cmp(&b.username)));
+    for (index, entry) in entries.iter_mut().enumerate() {
+        entry.rank = index + 1;
+    }
+    entries
+}
+
+#[test]
+fn build_series_test() {
+    // 2022-01-31 12:00 UTC and 2022-02-01 12:00 UTC
+    let jan = 1643630400000;
+    let feb = 1643716800000;
+    let decisions = vec![
+        (jan, Status::Approved),
+        (feb, Status::Denied),
+        (feb, Status::Approved),
+    ];
+    let series = build_series(
+        jan,
+        feb + 1,
+        &Bucket::Month,
+        0,
+        vec![jan, feb],
+        decisions.clone(),
+    );
+    assert_eq!(series.len(), 2);
+    assert_eq!(series[0].start, 1640995200000);
+    assert_eq!((series[0].discovered, series[0].approved), (1, 1));
+    assert_eq!(
+        (series[1].reviewed, series[1].approved, series[1].denied),
+        (2, 1, 1)
+    );
+
+    let series = build_series(jan, feb + 1, &Bucket::Day, 0, vec![], decisions.clone());
+    assert_eq!(series.len(), 2);
+    assert_eq!(series[1].start - series[0].start, 24 * 60 * 60 * 1000);
+
+    // Both days are in the week starting Monday 2022-01-31
+    let series = build_series(jan, feb + 1, &Bucket::Week, 0, vec![], decisions.clone());
+    assert_eq!(series.len(), 1);
+    assert_eq!(series[0].start, 1643587200000);
+    assert_eq!(series[0].reviewed, 3);
+
+    // At UTC+14 both days are already in February
+    let offset = 14 * 60 * 60 * 1000;
+    let series = build_series(jan, feb + 1, &Bucket::Month, offset, vec![], decisions);
+    assert_eq!(series.len(), 1);
+    assert_eq!(series[0].start, 1643673600000 - offset);
+    assert_eq!(series[0].reviewed, 3);
+}
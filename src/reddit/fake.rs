@@ -0,0 +1,157 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use futures::future::LocalBoxFuture;
+
+use crate::discovery::BOT;
+use crate::error::internal_error::InternalError;
+use crate::reddit::models::{Comment, RedditAccount, RedditPost, RedditProfile};
+use crate::reddit::RedditGateway;
+use crate::user::models::{SubmitUser, User};
+use crate::Titles;
+
+#[derive(Debug, Clone)]
+pub struct SentMessage {
+    pub username: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Default)]
+pub struct FakeState {
+    pub accounts: HashMap<String, RedditAccount>,
+    pub submissions: HashMap<String, Vec<RedditPost>>,
+    pub comments: HashMap<String, Vec<Comment>>,
+    pub contributors: HashSet<String>,
+    pub messages: Vec<SentMessage>,
+}
+
+/// A user the bot found with no titles loaded
+pub fn found_user(username: &str) -> User {
+    let submit = SubmitUser {
+        username: username.to_string(),
+        status: None,
+        moderator: None,
+        created: None,
+    };
+    User::new(submit, BOT.to_string(), &Titles { titles: vec![] })
+}
+
+/// An in memory Reddit. Nothing leaves the process
+#[derive(Default)]
+pub struct FakeReddit {
+    pub state: Mutex<FakeState>,
+}
+
+impl FakeReddit {
+    pub fn add_account(&self, username: &str) -> RedditAccount {
+        let account = RedditAccount {
+            name: username.to_string(),
+            icon_img: format!("https://example.com/{}.png", username),
+            snoovatar_img: "".to_string(),
+            comment_karma: 0,
+            total_karma: 0,
+            created: 0,
+            is_suspended: false,
+        };
+        self.state
+            .lock()
+            .unwrap()
+            .accounts
+            .insert(username.to_lowercase(), account.clone());
+        account
+    }
+    pub fn is_contributor(&self, username: &str) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .contributors
+            .contains(&username.to_lowercase())
+    }
+    pub fn messages(&self) -> Vec<SentMessage> {
+        self.state.lock().unwrap().messages.clone()
+    }
+}
+
+impl RedditGateway for FakeReddit {
+    fn user<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> LocalBoxFuture<'a, Result<Option<RedditAccount>, InternalError>> {
+        let account = self
+            .state
+            .lock()
+            .unwrap()
+            .accounts
+            .get(&username.to_lowercase())
+            .cloned();
+        Box::pin(async move { Ok(account) })
+    }
+
+    fn profile<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> LocalBoxFuture<'a, Result<Option<RedditProfile>, InternalError>> {
+        let state = self.state.lock().unwrap();
+        let key = username.to_lowercase();
+        let profile = state.accounts.get(&key).cloned().map(|account| {
+            if account.is_suspended {
+                return RedditProfile {
+                    account,
+                    submissions: Vec::new(),
+                    comments: Vec::new(),
+                };
+            }
+            RedditProfile {
+                account,
+                submissions: state.submissions.get(&key).cloned().unwrap_or_default(),
+                comments: state.comments.get(&key).cloned().unwrap_or_default(),
+            }
+        });
+        Box::pin(async move { Ok(profile) })
+    }
+
+    fn add_contributor<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> LocalBoxFuture<'a, Result<bool, InternalError>> {
+        let mut state = self.state.lock().unwrap();
+        let exists = state.accounts.contains_key(&username.to_lowercase());
+        if exists {
+            state.contributors.insert(username.to_lowercase());
+        }
+        Box::pin(async move { Ok(exists) })
+    }
+
+    fn remove_contributor<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> LocalBoxFuture<'a, Result<bool, InternalError>> {
+        let removed = self
+            .state
+            .lock()
+            .unwrap()
+            .contributors
+            .remove(&username.to_lowercase());
+        Box::pin(async move { Ok(removed) })
+    }
+
+    fn compose<'a>(
+        &'a self,
+        username: &'a str,
+        subject: &'a str,
+        body: &'a str,
+    ) -> LocalBoxFuture<'a, Result<(), InternalError>> {
+        let mut state = self.state.lock().unwrap();
+        if !state.accounts.contains_key(&username.to_lowercase()) {
+            let error = InternalError::Error(format!("Reddit does not know {}", username));
+            return Box::pin(async move { Err(error) });
+        }
+        state.messages.push(SentMessage {
+            username: username.to_string(),
+            subject: subject.to_string(),
+            body: body.to_string(),
+        });
+        Box::pin(async move { Ok(()) })
+    }
+}
@@ -0,0 +1,155 @@
+use futures::future::LocalBoxFuture;
+use rraw::auth::PasswordAuthenticator;
+use rraw::error::http_error::HTTPError;
+use rraw::utils::options::FriendType;
+use rraw::Client;
+
+use crate::error::internal_error::InternalError;
+use crate::reddit::models::{Comment, RedditAccount, RedditContent, RedditPost, RedditProfile};
+use crate::reddit::RedditGateway;
+
+/// The RedditGateway backed by the bot account
+pub struct LiveReddit {
+    pub client: Client<PasswordAuthenticator>,
+    //The subreddit contributors are added to and modmail is sent from
+    pub subreddit: String,
+}
+
+impl LiveReddit {
+    pub fn new(client: Client<PasswordAuthenticator>, subreddit: &str) -> LiveReddit {
+        LiveReddit {
+            client,
+            subreddit: subreddit.to_string(),
+        }
+    }
+
+    /// Looks the user up once. Submissions and comments are only loaded with activity and never for suspended accounts
+    async fn load(
+        &self,
+        username: &str,
+        activity: bool,
+    ) -> Result<Option<RedditProfile>, InternalError> {
+        let user = match self.client.user(username).await {
+            Ok(user) => user,
+            Err(rraw::error::Error::HTTPError(error)) if error.eq(&HTTPError::NotFound) => {
+                return Ok(None);
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let mut submissions = Vec::new();
+        let mut comments = Vec::new();
+        if activity && !user.user.is_suspended {
+            for x in user.submissions(None).await?.data.children {
+                let x = x.data;
+                let text = x.selftext;
+                let content = if text.is_empty() {
+                    RedditContent {
+                        content: None,
+                        url: x.url,
+                        over_18: x.over_18,
+                    }
+                } else {
+                    RedditContent {
+                        content: Some(text),
+                        url: None,
+                        over_18: x.over_18,
+                    }
+                };
+                submissions.push(RedditPost {
+                    subreddit: x.subreddit,
+                    url: format!("https://reddit.com{}", x.permalink),
+                    id: x.id.clone(),
+                    title: x.title.clone(),
+                    content,
+                    score: x.score as i64,
+                });
+            }
+            for x in user.comments(None).await?.data.children {
+                let x = x.data;
+                comments.push(Comment {
+                    subreddit: x.subreddit,
+                    url: format!("https://reddit.com{}", x.permalink),
+                    id: x.id.clone(),
+                    og_post_title: x.link_title.unwrap_or_default(),
+                    content: x.body,
+                    score: x.score as i64,
+                });
+            }
+        }
+        let user = user.user;
+        Ok(Some(RedditProfile {
+            account: RedditAccount {
+                name: user.name,
+                icon_img: user.icon_img,
+                snoovatar_img: user.snoovatar_img,
+                comment_karma: user.comment_karma,
+                total_karma: user.total_karma,
+                created: user.created as i64,
+                is_suspended: user.is_suspended,
+            },
+            submissions,
+            comments,
+        }))
+    }
+}
+
+impl RedditGateway for LiveReddit {
+    fn user<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> LocalBoxFuture<'a, Result<Option<RedditAccount>, InternalError>> {
+        Box::pin(async move {
+            let profile = self.load(username, false).await?;
+            Ok(profile.map(|profile| profile.account))
+        })
+    }
+
+    fn profile<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> LocalBoxFuture<'a, Result<Option<RedditProfile>, InternalError>> {
+        Box::pin(async move { self.load(username, true).await })
+    }
+
+    fn add_contributor<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> LocalBoxFuture<'a, Result<bool, InternalError>> {
+        Box::pin(async move {
+            let subreddit = self.client.subreddit(self.subreddit.as_str()).await?;
+            let friend = subreddit
+                .add_friend(username.to_string(), FriendType::Contributor)
+                .await?;
+            Ok(friend.success)
+        })
+    }
+
+    fn remove_contributor<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> LocalBoxFuture<'a, Result<bool, InternalError>> {
+        Box::pin(async move {
+            let subreddit = self.client.subreddit(self.subreddit.as_str()).await?;
+            let friend = subreddit
+                .remove_friend(username.to_string(), FriendType::Contributor)
+                .await?;
+            Ok(friend.success)
+        })
+    }
+
+    fn compose<'a>(
+        &'a self,
+        username: &'a str,
+        subject: &'a str,
+        body: &'a str,
+    ) -> LocalBoxFuture<'a, Result<(), InternalError>> {
+        Box::pin(async move {
+            self.client
+                .subreddit(self.subreddit.as_str())
+                .await?
+                .compose(username.to_string(), subject.to_string(), body.to_string())
+                .await?;
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,45 @@
+#[cfg(test)]
+pub mod fake;
+pub mod live;
+pub mod models;
+pub mod oauth;
+#[cfg(test)]
+pub mod stand_in;
+
+use futures::future::LocalBoxFuture;
+
+use crate::error::internal_error::InternalError;
+use crate::reddit::models::{RedditAccount, RedditProfile};
+
+/// Everything the site needs from Reddit. Controllers only talk to Reddit through this
+/// so the review and login flows can be tested with the fake
+pub trait RedditGateway: Send + Sync {
+    /// The about data of the user. None if the account does not exist
+    fn user<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> LocalBoxFuture<'a, Result<Option<RedditAccount>, InternalError>>;
+    /// The about data of the user with their latest submissions and comments.
+    /// Suspended accounts have no submissions or comments. None if the account does not exist
+    fn profile<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> LocalBoxFuture<'a, Result<Option<RedditProfile>, InternalError>>;
+    /// Adds the user as a contributor of the subreddit. Returns true if Reddit accepted it
+    fn add_contributor<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> LocalBoxFuture<'a, Result<bool, InternalError>>;
+    /// Removes the user as a contributor of the subreddit. Returns true if Reddit accepted it
+    fn remove_contributor<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> LocalBoxFuture<'a, Result<bool, InternalError>>;
+    /// Sends a modmail message from the subreddit to the user
+    fn compose<'a>(
+        &'a self,
+        username: &'a str,
+        subject: &'a str,
+        body: &'a str,
+    ) -> LocalBoxFuture<'a, Result<(), InternalError>>;
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+/// The about data of a Reddit account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedditAccount {
+    pub name: String,
+    pub icon_img: String,
+    pub snoovatar_img: String,
+    pub comment_karma: i64,
+    pub total_karma: i64,
+    pub created: i64,
+    pub is_suspended: bool,
+}
+
+/// A Reddit account with its latest activity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedditProfile {
+    pub account: RedditAccount,
+    pub submissions: Vec<RedditPost>,
+    pub comments: Vec<Comment>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedditPost {
+    pub subreddit: String,
+    pub url: String,
+    pub id: String,
+    pub title: String,
+    pub content: RedditContent,
+    pub score: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comment {
+    pub subreddit: String,
+    pub url: String,
+    pub id: String,
+    pub og_post_title: String,
+    pub content: String,
+    pub score: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedditContent {
+    pub content: Option<String>,
+    pub url: Option<String>,
+    pub over_18: bool,
+}
@@ -0,0 +1,213 @@
+use std::time::Duration;
+
+use hyper::body::Bytes;
+use hyper::client::HttpConnector;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE, USER_AGENT};
+use hyper::{Body, Client, Method, Request, StatusCode};
+use hyper_tls::HttpsConnector;
+use serde::{Deserialize, Serialize};
+
+use crate::error::internal_error::InternalError;
+use crate::user::utils::random_string;
+
+const AUTHORIZE_URL: &str = "https://www.reddit.com/api/v1/authorize";
+const TOKEN_URL: &str = "https://www.reddit.com/api/v1/access_token";
+const API_URL: &str = "https://oauth.reddit.com";
+const AGENT: &str = "RedditNobility Login(by u/KingTuxWH)";
+/// How long the user has to log in on Reddit. In seconds
+pub const STATE_LIFETIME: i64 = 600;
+const STATE_LENGTH: usize = 32;
+/// Reddit gets this long to answer each request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize)]
+struct AuthorizeQuery<'a> {
+    client_id: &'a str,
+    response_type: &'a str,
+    state: &'a str,
+    redirect_uri: &'a str,
+    duration: &'a str,
+    scope: &'a str,
+}
+
+#[derive(Serialize)]
+struct TokenForm<'a> {
+    grant_type: &'a str,
+    code: &'a str,
+    redirect_uri: &'a str,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Identity {
+    name: String,
+}
+
+/// Logs users in with the Reddit authorization code flow.
+/// The Reddit URLs can be changed so it can be pointed at a local stand-in
+pub struct RedditOAuth {
+    pub client_id: String,
+    pub client_secret: String,
+    //The page Reddit sends the user back to with the code
+    pub redirect_uri: String,
+    pub authorize_url: String,
+    pub token_url: String,
+    //Where the identity is read with the access token
+    pub api_url: String,
+    client: Client<HttpsConnector<HttpConnector>>,
+}
+
+impl RedditOAuth {
+    pub fn new(client_id: String, client_secret: String, redirect_uri: String) -> RedditOAuth {
+        RedditOAuth {
+            client_id,
+            client_secret,
+            redirect_uri,
+            authorize_url: AUTHORIZE_URL.to_string(),
+            token_url: TOKEN_URL.to_string(),
+            api_url: API_URL.to_string(),
+            client: Client::builder().build::<_, hyper::Body>(HttpsConnector::new()),
+        }
+    }
+
+    /// None if the OAUTH variables are not set
+    pub fn from_env() -> Option<RedditOAuth> {
+        let mut oauth = RedditOAuth::new(
+            std::env::var("OAUTH_CLIENT_KEY").ok()?,
+            std::env::var("OAUTH_CLIENT_SECRET").ok()?,
+            std::env::var("OAUTH_REDIRECT").ok()?,
+        );
+        if let Ok(url) = std::env::var("REDDIT_AUTHORIZE_URL") {
+            oauth.authorize_url = url;
+        }
+        if let Ok(url) = std::env::var("REDDIT_TOKEN_URL") {
+            oauth.token_url = url;
+        }
+        if let Ok(url) = std::env::var("REDDIT_API_URL") {
+            oauth.api_url = url;
+        }
+        Some(oauth)
+    }
+
+    /// A new state to tie the login to the browser that started it
+    pub fn new_state() -> String {
+        random_string(STATE_LENGTH)
+    }
+
+    /// The URL to send the user to
+    pub fn authorize(&self, state: &str) -> Result<String, InternalError> {
+        let query = serde_qs::to_string(&AuthorizeQuery {
+            client_id: &self.client_id,
+            response_type: "code",
+            state,
+            redirect_uri: &self.redirect_uri,
+            duration: "temporary",
+            scope: "identity",
+        })
+        .map_err(|error| InternalError::Error(error.to_string()))?;
+        Ok(format!("{}?{}", self.authorize_url, query))
+    }
+
+    /// Trades the code for an access token and returns the name of the Reddit account.
+    /// None if Reddit rejected the code
+    pub async fn identify(&self, code: &str) -> Result<Option<String>, InternalError> {
+        let form = serde_qs::to_string(&TokenForm {
+            grant_type: "authorization_code",
+            code,
+            redirect_uri: &self.redirect_uri,
+        })
+        .map_err(|error| InternalError::Error(error.to_string()))?;
+        let credentials = base64::encode(format!("{}:{}", self.client_id, self.client_secret));
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(&self.token_url)
+            .header(AUTHORIZATION, format!("Basic {}", credentials))
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .header(USER_AGENT, AGENT)
+            .body(Body::from(form))
+            .map_err(|error| InternalError::Error(error.to_string()))?;
+        let (status, body) = self.send(request).await?;
+        if !status.is_success() {
+            return Err(InternalError::Error(format!(
+                "Reddit Token returned {}",
+                status
+            )));
+        }
+        let token: TokenResponse = serde_json::from_slice(&body)?;
+        if token.error.is_some() || token.access_token.is_none() {
+            return Ok(None);
+        }
+
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(format!("{}/api/v1/me", self.api_url))
+            .header(
+                AUTHORIZATION,
+                format!("bearer {}", token.access_token.unwrap()),
+            )
+            .header(USER_AGENT, AGENT)
+            .body(Body::empty())
+            .map_err(|error| InternalError::Error(error.to_string()))?;
+        let (status, body) = self.send(request).await?;
+        if !status.is_success() {
+            return Err(InternalError::Error(format!(
+                "Reddit Identity returned {}",
+                status
+            )));
+        }
+        let identity: Identity = serde_json::from_slice(&body)?;
+        Ok(Some(identity.name))
+    }
+
+    async fn send(&self, request: Request<Body>) -> Result<(StatusCode, Bytes), InternalError> {
+        let response = async {
+            let response = self.client.request(request).await?;
+            let status = response.status();
+            let bytes = hyper::body::to_bytes(response.into_body()).await?;
+            Ok::<_, InternalError>((status, bytes))
+        };
+        tokio::time::timeout(REQUEST_TIMEOUT, response)
+            .await
+            .map_err(|_| InternalError::Error("Reddit did not respond in time".to_string()))?
+    }
+}
+
+#[test]
+fn oauth_authorize_test() {
+    let mut oauth = RedditOAuth::new(
+        "client".to_string(),
+        "secret".to_string(),
+        "http://localhost:8080/login/reddit".to_string(),
+    );
+    oauth.authorize_url = "http://localhost:9000/authorize".to_string();
+    let state = RedditOAuth::new_state();
+    let url = oauth.authorize(&state).unwrap();
+    assert!(url.starts_with("http://localhost:9000/authorize?client_id=client"));
+    assert!(url.contains("scope=identity"));
+    assert!(url.contains(&format!("state={}", state)));
+}
+
+#[actix_web::test]
+async fn identify_test() {
+    use crate::reddit::stand_in;
+
+    let base = stand_in::start();
+    let oauth = stand_in::oauth(&base);
+    assert_eq!(
+        oauth.identify(stand_in::GOOD_CODE).await.unwrap(),
+        Some(stand_in::USERNAME.to_string())
+    );
+    // Reddit answers a bad code with an error in the body
+    assert_eq!(oauth.identify("used").await.unwrap(), None);
+    // The token is not accepted by /api/v1/me
+    assert!(oauth.identify(stand_in::REVOKED_CODE).await.is_err());
+
+    let mut wrong_secret = stand_in::oauth(&base);
+    wrong_secret.client_secret = "wrong".to_string();
+    assert!(wrong_secret.identify(stand_in::GOOD_CODE).await.is_err());
+}
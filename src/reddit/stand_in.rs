@@ -0,0 +1,85 @@
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::web::Form;
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::reddit::oauth::RedditOAuth;
+
+pub const CLIENT_ID: &str = "client";
+pub const CLIENT_SECRET: &str = "secret";
+pub const USERNAME: &str = "KingTuxWH";
+/// Logs in as USERNAME
+pub const GOOD_CODE: &str = "good";
+/// Gets an access token that /api/v1/me does not accept
+pub const REVOKED_CODE: &str = "revoked";
+const TOKEN: &str = "token";
+
+#[derive(Deserialize)]
+struct TokenForm {
+    grant_type: String,
+    code: String,
+}
+
+fn header(request: &HttpRequest) -> String {
+    request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+async fn access_token(form: Form<TokenForm>, request: HttpRequest) -> HttpResponse {
+    let credentials = base64::encode(format!("{}:{}", CLIENT_ID, CLIENT_SECRET));
+    if header(&request) != format!("Basic {}", credentials) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let token = match form.code.as_str() {
+        GOOD_CODE => Some(TOKEN),
+        REVOKED_CODE => Some("revoked"),
+        _ => None,
+    }
+    .filter(|_| form.grant_type == "authorization_code");
+    match token {
+        Some(token) => {
+            HttpResponse::Ok().json(json!({"access_token": token, "token_type": "bearer"}))
+        }
+        None => HttpResponse::Ok().json(json!({"error": "invalid_grant"})),
+    }
+}
+
+async fn me(request: HttpRequest) -> HttpResponse {
+    if header(&request) != format!("bearer {}", TOKEN) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    HttpResponse::Ok().json(json!({ "name": USERNAME }))
+}
+
+/// Starts a local stand-in for the Reddit OAuth endpoints. Returns the base URL
+pub fn start() -> String {
+    let server = HttpServer::new(|| {
+        App::new()
+            .route("/api/v1/access_token", web::post().to(access_token))
+            .route("/api/v1/me", web::get().to(me))
+    })
+    .workers(1)
+    .bind(("127.0.0.1", 0))
+    .unwrap();
+    let address = server.addrs()[0];
+    actix_web::rt::spawn(server.run());
+    format!("http://{}", address)
+}
+
+/// A RedditOAuth that talks to the stand-in at base
+pub fn oauth(base: &str) -> RedditOAuth {
+    let mut oauth = RedditOAuth::new(
+        CLIENT_ID.to_string(),
+        CLIENT_SECRET.to_string(),
+        "http://localhost:8080/login/reddit".to_string(),
+    );
+    oauth.authorize_url = format!("{}/api/v1/authorize", base);
+    oauth.token_url = format!("{}/api/v1/access_token", base);
+    oauth.api_url = base.to_string();
+    oauth
+}
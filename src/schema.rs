@@ -5,7 +5,6 @@ table! {
         username -> Text,
         password -> Text,
         password_changed -> Bigint,
-        permissions -> Text,
         status -> Text,
         status_changed ->Bigint,
         discoverer -> Text,
@@ -14,14 +13,19 @@ table! {
         title -> Text,
         birthday -> Nullable<Text>,
         created ->Bigint,
+        title_manual -> Bool,
     }
 }
 table! {
     auth_tokens (id) {
         id -> Bigint,
         user -> Bigint,
+        selector -> Text,
         token -> Text,
         created ->Bigint,
+        expiration -> Bigint,
+        last_used -> Bigint,
+        user_agent -> Nullable<Text>,
     }
 }
 table! {
@@ -60,3 +64,108 @@ table! {
 
     }
 }
+table! {
+    review_claims (id) {
+        id -> Bigint,
+        user -> Bigint,
+        reviewer -> Text,
+        expiration ->Bigint,
+        created ->Bigint,
+
+    }
+}
+table! {
+    discovery_runs (id) {
+        id -> Bigint,
+        source -> Text,
+        started ->Bigint,
+        finished ->Bigint,
+        scanned ->Bigint,
+        matched ->Bigint,
+        inserted ->Bigint,
+        errors ->Bigint,
+
+    }
+}
+table! {
+    titles (id) {
+        id -> Bigint,
+        value -> Text,
+        proper_name -> Text,
+        description -> Nullable<Text>,
+        color -> Nullable<Text>,
+        possible_titles -> Nullable<Text>,
+        priority -> Nullable<Bigint>,
+        exclude -> Nullable<Text>,
+        position -> Nullable<Text>,
+        word_boundary -> Nullable<Bool>,
+        leetspeak -> Nullable<Bool>,
+        ordering ->Bigint,
+        retired ->Bool,
+        edited ->Bool,
+        created ->Bigint,
+        updated ->Bigint,
+
+    }
+}
+table! {
+    audit_events (id) {
+        id -> Bigint,
+        actor -> Text,
+        target -> Text,
+        action -> Text,
+        old_value -> Nullable<Text>,
+        new_value -> Nullable<Text>,
+        created -> Bigint,
+    }
+}
+table! {
+    status_history (id) {
+        id -> Bigint,
+        user -> Bigint,
+        old_status -> Text,
+        status -> Text,
+        reviewer -> Text,
+        title -> Text,
+        note -> Nullable<Text>,
+        created -> Bigint,
+    }
+}
+table! {
+    review_decisions (id) {
+        id -> Bigint,
+        user -> Bigint,
+        reviewer -> Text,
+        status -> Text,
+        reason -> Nullable<Text>,
+        note -> Nullable<Text>,
+        created -> Bigint,
+    }
+}
+table! {
+    roles (id) {
+        id -> Bigint,
+        name -> Text,
+        permissions -> Text,
+        is_default -> Bool,
+        created -> Bigint,
+    }
+}
+table! {
+    user_roles (id) {
+        id -> Bigint,
+        user -> Bigint,
+        role -> Bigint,
+        created -> Bigint,
+    }
+}
+table! {
+    otp_attempts (id) {
+        id -> Bigint,
+        user -> Bigint,
+        ip -> Text,
+        created -> Bigint,
+    }
+}
+allow_tables_to_appear_in_same_query!(users, review_claims);
+allow_tables_to_appear_in_same_query!(roles, user_roles);
@@ -7,6 +7,7 @@ use crate::settings::models::{
 };
 use crate::utils::get_current_time;
 use diesel::MysqlConnection;
+use std::str::FromStr;
 
 pub fn quick_add(key: &str, value: String, conn: &MysqlConnection) -> Result<(), InternalError> {
     let result = get_setting(key, conn)?;
@@ -35,6 +36,16 @@ pub fn get_setting_or_empty(
     }
 }
 
+/// Reads the setting or its default and parses it into the requested type
+pub fn get_setting_value<T: FromStr>(
+    string: &str,
+    connection: &MysqlConnection,
+) -> Result<T, InternalError> {
+    let setting = get_setting_or_empty(string, connection)?;
+    T::from_str(&setting.value)
+        .map_err(|_| InternalError::Error(format!("Invalid value for setting {}", string)))
+}
+
 pub fn default_string() -> String {
     "".to_string()
 }
@@ -1,8 +1,11 @@
-use crate::user::models::{AuthToken, TeamMember, TeamUser, User, UserProperties, OTP, BackupUser};
+use crate::user::models::{AuthToken, TeamMember, TeamUser, User, UserProperties, OTP, BackupUser, OTPAttempt};
+use diesel::dsl::count_star;
 use diesel::prelude::*;
 use diesel::result::Error as DieselError;
 use diesel::MysqlConnection;
 use crate::get_current_time;
+use crate::user::role::action::{get_all_user_roles, get_roles};
+use std::collections::HashMap;
 
 pub fn add_new_user(user: &User, conn: &MysqlConnection) -> Result<(), diesel::result::Error> {
     use crate::schema::users::dsl::*;
@@ -44,9 +47,17 @@ pub fn get_user_by_id(
     users.filter(id.eq(l_id)).first::<User>(conn).optional()
 }
 
-pub fn get_found_users(conn: &MysqlConnection) -> Result<Vec<User>, diesel::result::Error> {
+/// Users waiting for a second review that someone other than the reviewer approved. The oldest first
+pub fn get_pending_second_review(
+    not_reviewer: &str,
+    conn: &MysqlConnection,
+) -> Result<Vec<User>, diesel::result::Error> {
     use crate::schema::users::dsl::*;
-    users.filter(status.eq("Found")).load::<User>(conn)
+    users
+        .filter(status.eq("PendingSecondReview"))
+        .filter(reviewer.ne(not_reviewer))
+        .order(status_changed.asc())
+        .load::<User>(conn)
 }
 
 pub fn get_users(conn: &MysqlConnection) -> Result<Vec<User>, diesel::result::Error> {
@@ -94,6 +105,7 @@ pub fn update_properties(
     Ok(())
 }
 
+/// Sets the title by hand. The title will no longer be changed when the titles are reapplied
 pub fn update_title(
     user: &i64,
     tit: &str,
@@ -102,11 +114,35 @@ pub fn update_title(
     use crate::schema::users::dsl::*;
 
     diesel::update(users.filter(id.eq(user)))
-        .set((title.eq(tit), ))
+        .set((title.eq(tit), title_manual.eq(true)))
         .execute(conn)?;
     Ok(())
 }
 
+/// Id, username, title and if the title was set by hand for every user
+pub fn get_user_titles(
+    conn: &MysqlConnection,
+) -> Result<Vec<(i64, String, String, bool)>, diesel::result::Error> {
+    use crate::schema::users::dsl::*;
+    users
+        .select((id, username, title, title_manual))
+        .order(id.asc())
+        .load(conn)
+}
+
+/// Sets a title found by the matcher. Titles set by hand are not touched
+pub fn update_matched_title(
+    user: &i64,
+    tit: &str,
+    conn: &MysqlConnection,
+) -> Result<usize, diesel::result::Error> {
+    use crate::schema::users::dsl::*;
+
+    diesel::update(users.filter(id.eq(user)).filter(title_manual.eq(false)))
+        .set(title.eq(tit))
+        .execute(conn)
+}
+
 pub fn update_password(
     user: &i64,
     pass: String,
@@ -120,33 +156,33 @@ pub fn update_password(
     Ok(())
 }
 
-pub fn get_user_from_auth_token(
-    token: String,
+//Auth Token
+/// The tokens starting with the selector. The token still has to be checked against the hash
+pub fn get_auth_tokens_by_selector(
+    s: &str,
     conn: &MysqlConnection,
-) -> Result<Option<User>, diesel::result::Error> {
-    let result = get_auth_token(token, conn);
-    if result.is_err() {
-        return Err(result.err().unwrap());
-    }
-    let result = result.unwrap();
-    if result.is_none() {
-        return Ok(None);
-    }
-    let result = result.unwrap();
-    get_user_by_id(&result.user, conn)
+) -> Result<Vec<AuthToken>, DieselError> {
+    use crate::schema::auth_tokens::dsl::*;
+    auth_tokens.filter(selector.eq(s)).load::<AuthToken>(conn)
 }
 
-//Auth Token
-pub fn get_auth_token(
-    a_token: String,
+/// Tokens from before they were hashed. They have no selector
+pub fn get_legacy_auth_tokens(conn: &MysqlConnection) -> Result<Vec<AuthToken>, DieselError> {
+    use crate::schema::auth_tokens::dsl::*;
+    auth_tokens.filter(selector.eq("")).load::<AuthToken>(conn)
+}
+
+pub fn update_auth_token_hash(
+    t_id: &i64,
+    s: &str,
+    hash: &str,
     conn: &MysqlConnection,
-) -> Result<Option<AuthToken>, diesel::result::Error> {
+) -> Result<(), DieselError> {
     use crate::schema::auth_tokens::dsl::*;
-    let found_token = auth_tokens
-        .filter(token.eq(a_token))
-        .first::<AuthToken>(conn)
-        .optional()?;
-    Ok(found_token)
+    diesel::update(auth_tokens.filter(id.eq(t_id)))
+        .set((selector.eq(s), token.eq(hash)))
+        .execute(conn)?;
+    Ok(())
 }
 
 pub fn add_new_auth_token(
@@ -161,29 +197,134 @@ pub fn add_new_auth_token(
     Ok(())
 }
 
-pub fn get_opt(value: &str, conn: &MysqlConnection) -> Result<Option<OTP>, diesel::result::Error> {
+/// Logs the user out everywhere
+pub fn delete_auth_tokens(u: &i64, conn: &MysqlConnection) -> Result<usize, DieselError> {
+    use crate::schema::auth_tokens::dsl::*;
+    diesel::delete(auth_tokens).filter(user.eq(u)).execute(conn)
+}
+
+/// Pushes the expiration of a token that is being used
+pub fn renew_auth_token(
+    t_id: &i64,
+    expires: i64,
+    used: i64,
+    conn: &MysqlConnection,
+) -> Result<(), DieselError> {
+    use crate::schema::auth_tokens::dsl::*;
+    diesel::update(auth_tokens.filter(id.eq(t_id)))
+        .set((expiration.eq(expires), last_used.eq(used)))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// The tokens of the user that have not expired. Most recently used first
+pub fn get_auth_tokens(u: &i64, conn: &MysqlConnection) -> Result<Vec<AuthToken>, DieselError> {
+    use crate::schema::auth_tokens::dsl::*;
+    auth_tokens
+        .filter(user.eq(u).and(expiration.gt(get_current_time())))
+        .order(last_used.desc())
+        .load::<AuthToken>(conn)
+}
+
+/// Logs out a single session of the user
+pub fn delete_auth_token(
+    t_id: &i64,
+    u: &i64,
+    conn: &MysqlConnection,
+) -> Result<usize, DieselError> {
+    use crate::schema::auth_tokens::dsl::*;
+    diesel::delete(auth_tokens)
+        .filter(id.eq(t_id).and(user.eq(u)))
+        .execute(conn)
+}
+
+/// Logs the user out everywhere but the session they are using
+pub fn delete_other_auth_tokens(
+    u: &i64,
+    keep: &i64,
+    conn: &MysqlConnection,
+) -> Result<usize, DieselError> {
+    use crate::schema::auth_tokens::dsl::*;
+    diesel::delete(auth_tokens)
+        .filter(user.eq(u).and(id.ne(keep)))
+        .execute(conn)
+}
+
+pub fn delete_expired_auth_tokens(conn: &MysqlConnection) -> Result<usize, DieselError> {
+    use crate::schema::auth_tokens::dsl::*;
+    diesel::delete(auth_tokens)
+        .filter(expiration.le(get_current_time()))
+        .execute(conn)
+}
+
+/// The one time passwords of the user that have not expired.
+/// They are stored hashed so they have to be checked one by one
+pub fn get_otps(u: &i64, conn: &MysqlConnection) -> Result<Vec<OTP>, DieselError> {
     use crate::schema::otps::dsl::*;
-    let x: Option<OTP> = otps
-        .filter(password.eq(value))
-        .first::<OTP>(conn)
-        .optional()?;
-    Ok(x)
+    otps.filter(user.eq(u).and(expiration.gt(get_current_time())))
+        .load::<OTP>(conn)
 }
 
-pub fn delete_otp(otp_id: i64, conn: &MysqlConnection) -> Result<(), DieselError> {
+pub fn delete_expired_otps(conn: &MysqlConnection) -> Result<usize, DieselError> {
     use crate::schema::otps::dsl::*;
-    diesel::delete(otps).filter(id.eq(otp_id)).execute(conn)?;
+    diesel::delete(otps)
+        .filter(expiration.le(get_current_time()))
+        .execute(conn)
+}
+
+pub fn add_otp_attempt(value: &OTPAttempt, conn: &MysqlConnection) -> Result<(), DieselError> {
+    use crate::schema::otp_attempts::dsl::*;
+    diesel::insert_into(otp_attempts)
+        .values(value)
+        .execute(conn)?;
     Ok(())
 }
 
-pub fn opt_exist(value: &str, conn: &MysqlConnection) -> Result<bool, diesel::result::Error> {
-    use crate::schema::otps::dsl::*;
-    let x: Option<i64> = otps
-        .select(id)
-        .filter(password.eq(value))
+/// Failed attempts for the user since the time
+pub fn count_otp_attempts_by_user(
+    u: &i64,
+    since: i64,
+    conn: &MysqlConnection,
+) -> Result<i64, DieselError> {
+    use crate::schema::otp_attempts::dsl::*;
+    otp_attempts
+        .filter(user.eq(u).and(created.ge(since)))
+        .select(count_star())
         .first(conn)
-        .optional()?;
-    Ok(x.is_some())
+}
+
+/// Failed attempts from the IP since the time
+pub fn count_otp_attempts_by_ip(
+    i: &str,
+    since: i64,
+    conn: &MysqlConnection,
+) -> Result<i64, DieselError> {
+    use crate::schema::otp_attempts::dsl::*;
+    otp_attempts
+        .filter(ip.eq(i).and(created.ge(since)))
+        .select(count_star())
+        .first(conn)
+}
+
+/// Clears the failed attempts of the user after they log in
+pub fn delete_otp_attempts(u: &i64, conn: &MysqlConnection) -> Result<usize, DieselError> {
+    use crate::schema::otp_attempts::dsl::*;
+    diesel::delete(otp_attempts)
+        .filter(user.eq(u))
+        .execute(conn)
+}
+
+pub fn delete_old_otp_attempts(before: i64, conn: &MysqlConnection) -> Result<usize, DieselError> {
+    use crate::schema::otp_attempts::dsl::*;
+    diesel::delete(otp_attempts)
+        .filter(created.lt(before))
+        .execute(conn)
+}
+
+/// Returns 0 if someone else already used it
+pub fn delete_otp(otp_id: i64, conn: &MysqlConnection) -> Result<usize, DieselError> {
+    use crate::schema::otps::dsl::*;
+    diesel::delete(otps).filter(id.eq(otp_id)).execute(conn)
 }
 
 pub fn add_opt(value: &OTP, conn: &MysqlConnection) -> Result<(), DieselError> {
@@ -199,6 +340,18 @@ pub fn get_team_members(conn: &MysqlConnection) -> Result<Vec<TeamMember>, Diese
     team_members.load::<TeamMember>(conn)
 }
 
+/// The usernames of the users with the ids
+pub fn get_usernames(
+    ids: &[i64],
+    conn: &MysqlConnection,
+) -> Result<Vec<(i64, String)>, diesel::result::Error> {
+    use crate::schema::users::dsl::*;
+    users
+        .filter(id.eq_any(ids))
+        .select((id, username))
+        .load(conn)
+}
+
 pub fn get_team_member(u: &i64, conn: &MysqlConnection) -> Result<Option<TeamMember>, DieselError> {
     use crate::schema::team_members::dsl::*;
 
@@ -220,7 +373,25 @@ pub fn get_team_user(
         .optional()
 }
 
+/// Every user with the names of their roles
 pub fn get_users_for_backup(conn: &MysqlConnection) -> Result<Vec<BackupUser>, diesel::result::Error> {
     use crate::schema::users::dsl::*;
-    users.select((username, permissions, status, status_changed, discoverer, reviewer, properties, title, birthday, created)).load::<BackupUser>(conn)
+    let names: HashMap<i64, String> = get_roles(conn)?
+        .into_iter()
+        .map(|role| (role.id, role.name))
+        .collect();
+    let mut assigned: HashMap<i64, Vec<String>> = HashMap::new();
+    for value in get_all_user_roles(conn)? {
+        if let Some(name) = names.get(&value.role) {
+            assigned.entry(value.user).or_default().push(name.clone());
+        }
+    }
+    let values = users.order(id.asc()).load::<User>(conn)?;
+    Ok(values
+        .into_iter()
+        .map(|user| {
+            let roles = assigned.remove(&user.id).unwrap_or_default();
+            BackupUser::new(user, roles)
+        })
+        .collect())
 }
@@ -0,0 +1,185 @@
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use actix_web::dev::Payload;
+use actix_web::http::StatusCode;
+use actix_web::{FromRequest, HttpRequest, HttpResponse, ResponseError};
+use futures::future::{ready, Ready};
+use log::error;
+
+use crate::error::internal_error::InternalError;
+use crate::error::response::unauthorized;
+use crate::user::models::{AuthToken, Status, User};
+use crate::user::role::action::get_user_permissions;
+use crate::user::role::models::{Permission, Permissions};
+use crate::user::utils::get_session_by_header;
+use crate::Database;
+
+/// Something the user has to be allowed to do to use a route
+pub trait Requirement {
+    fn allowed(user: &User, permissions: &Permissions) -> bool;
+}
+
+/// Any session. Includes accounts still in the review queue
+pub struct RequireSession;
+
+/// Any user that has left the review queue and still has the login permission.
+/// Taking login away from a role locks its users out of their existing sessions
+pub struct RequireLogin;
+
+/// Users that can approve or deny users in the review queue
+pub struct RequireReviewer;
+
+impl Requirement for RequireSession {
+    fn allowed(_user: &User, _permissions: &Permissions) -> bool {
+        true
+    }
+}
+
+impl Requirement for RequireLogin {
+    fn allowed(user: &User, permissions: &Permissions) -> bool {
+        user.status != Status::Found && permissions.contains(&Permission::Login)
+    }
+}
+
+impl Requirement for RequireReviewer {
+    fn allowed(_user: &User, permissions: &Permissions) -> bool {
+        permissions.contains(&Permission::ReviewApprove)
+            || permissions.contains(&Permission::ReviewDeny)
+    }
+}
+
+macro_rules! permission_requirement {
+    ($name:ident, $permission:expr) => {
+        pub struct $name;
+
+        impl Requirement for $name {
+            fn allowed(_user: &User, permissions: &Permissions) -> bool {
+                permissions.contains(&$permission)
+            }
+        }
+    };
+}
+
+permission_requirement!(RequireReviewRemove, Permission::ReviewRemove);
+permission_requirement!(RequireUsersView, Permission::UsersView);
+permission_requirement!(RequireUsersEdit, Permission::UsersEdit);
+permission_requirement!(RequireTeamEdit, Permission::TeamEdit);
+permission_requirement!(RequireSettingsEdit, Permission::SettingsEdit);
+permission_requirement!(RequireTitlesEdit, Permission::TitlesEdit);
+permission_requirement!(RequireRolesEdit, Permission::RolesEdit);
+permission_requirement!(RequireDiscoveryRun, Permission::DiscoveryRun);
+permission_requirement!(RequireAuditView, Permission::AuditView);
+
+/// The user behind the bearer token of the request. The request is rejected with the standard
+/// unauthorized response if there is no user or they do not meet the requirement
+pub struct Authenticated<R: Requirement = RequireLogin> {
+    pub user: User,
+    //The token the request was made with
+    pub session: AuthToken,
+    //Everything the user can do through their roles
+    pub permissions: Permissions,
+    requirement: PhantomData<R>,
+}
+
+impl<R: Requirement> Authenticated<R> {
+    pub fn has(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+
+    pub fn into_inner(self) -> User {
+        self.user
+    }
+}
+
+impl<R: Requirement> Deref for Authenticated<R> {
+    type Target = User;
+
+    fn deref(&self) -> &User {
+        &self.user
+    }
+}
+
+impl<R: Requirement> FromRequest for Authenticated<R> {
+    type Error = AuthenticationError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(authenticate(req))
+    }
+}
+
+fn authenticate<R: Requirement>(
+    req: &HttpRequest,
+) -> Result<Authenticated<R>, AuthenticationError> {
+    let database = req
+        .app_data::<Database>()
+        .ok_or_else(|| InternalError::Error("Missing Database".to_string()))?;
+    let conn = database.get().map_err(InternalError::from)?;
+    let session = get_session_by_header(req.headers(), &conn)?;
+    if session.is_none() {
+        return Err(AuthenticationError::Unauthorized);
+    }
+    let (user, session) = session.unwrap();
+    // Roles only count once the account has left the review queue and can still log in
+    let mut permissions = if user.status == Status::Found {
+        Permissions::default()
+    } else {
+        get_user_permissions(&user.id, &conn).map_err(InternalError::from)?
+    };
+    if !permissions.contains(&Permission::Login) {
+        permissions = Permissions::default();
+    }
+    if !R::allowed(&user, &permissions) {
+        return Err(AuthenticationError::Unauthorized);
+    }
+    Ok(Authenticated {
+        user,
+        session,
+        permissions,
+        requirement: PhantomData,
+    })
+}
+
+#[derive(Debug)]
+pub enum AuthenticationError {
+    Unauthorized,
+    Internal(InternalError),
+}
+
+impl From<InternalError> for AuthenticationError {
+    fn from(error: InternalError) -> Self {
+        AuthenticationError::Internal(error)
+    }
+}
+
+impl Display for AuthenticationError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            AuthenticationError::Unauthorized => write!(f, "Unauthorized"),
+            AuthenticationError::Internal(error) => write!(f, "{:?}", error),
+        }
+    }
+}
+
+impl ResponseError for AuthenticationError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthenticationError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AuthenticationError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            AuthenticationError::Unauthorized => {
+                unauthorized().unwrap_or_else(|error| error.json_error())
+            }
+            AuthenticationError::Internal(error) => {
+                error!("Unable to Authenticate {:?}", error);
+                error.json_error()
+            }
+        }
+    }
+}
@@ -1,42 +1,43 @@
 use actix_web::web::{Json, Path};
 use actix_web::{post, HttpRequest};
-use rraw::error::Error::HTTPError;
-use rraw::error::http_error::HTTPError::NotFound;
 
 use crate::api_response::{APIResponse, SiteResponse};
+use crate::audit::models::AuditAction;
+use crate::audit::utils::audit;
 use crate::error::internal_error::InternalError::Error;
-use crate::error::response::{already_exists, bad_request, not_found, unauthorized};
-use crate::user::action::{get_user_by_name, update_properties};
-use crate::user::utils::{get_user_by_header, quick_add};
+use crate::error::response::{already_exists, bad_request, not_found};
+use crate::user::action::{delete_other_auth_tokens, get_user_by_name, update_properties};
+use crate::user::authentication::Authenticated;
+use crate::user::role::models::Permission;
+use crate::user::utils::quick_add;
 use crate::{Database, RedditClient, TitleData};
 
 #[post("/api/submit/{username}")]
 pub async fn submit_user(
     pool: Database,
+    discoverer: Authenticated,
     suggest: Path<String>,
     r: HttpRequest,
     reddit_client: RedditClient,
     titles: TitleData,
 ) -> SiteResponse {
     let conn = pool.get()?;
-    let option = get_user_by_header(r.headers(), &conn)?;
-    if option.is_none() {
-        return unauthorized();
-    }
-
-    let discoverer = option.unwrap();
     let suggest = suggest.into_inner();
     let result1 = get_user_by_name(&suggest, &conn)?;
     if result1.is_some() {
         return already_exists();
     }
-    let user_reddit = reddit_client.user(suggest.clone()).await?;
-    quick_add(&suggest, &discoverer.username, &conn, &titles)?;
+    if reddit_client.user(&suggest).await?.is_none() {
+        return not_found();
+    }
+    if quick_add(&suggest, &discoverer.username, &conn, &titles.get())? {
+        audit(&discoverer.username, &suggest, AuditAction::Submit, None, None, &conn)?;
+    }
     let result1 = get_user_by_name(&suggest, &conn)?;
     if result1.is_none() {
         return Err(Error("Bad Creation?".to_string()));
     }
-    if discoverer.permissions.submit {
+    if discoverer.has(Permission::Submit) {
         return APIResponse::respond_new(result1, &r);
     }
     APIResponse {
@@ -55,16 +56,14 @@ pub struct ChangeRequest {
 #[post("/api/me/update/{key}")]
 pub async fn change_property(
     database: Database,
+    user: Authenticated,
     request: Json<ChangeRequest>,
     key: Path<String>,
     r: HttpRequest,
 ) -> SiteResponse {
     let conn = database.get()?;
-    let option = get_user_by_header(r.headers(), &conn)?;
-    if option.is_none() {
-        return unauthorized();
-    }
-    let mut user = option.unwrap();
+    let mut user = user.into_inner();
+    let old = serde_json::to_string(&user.properties)?;
     let value = request.0.value;
     match key.as_str() {
         "avatar" => {
@@ -77,23 +76,31 @@ pub async fn change_property(
             return bad_request("You can only change your Avatar or Description");
         }
     }
+    let new = serde_json::to_string(&user.properties)?;
     update_properties(&user.id, user.properties, &conn)?;
+    audit(
+        &user.username,
+        &user.username,
+        AuditAction::PropertyChange,
+        Some(old),
+        Some(new),
+        &conn,
+    )?;
     APIResponse::new(true, Some(true)).respond(&r)
 }
 
 #[post("/api/me/password/change")]
 pub async fn update_password(
     database: Database,
+    user: Authenticated,
     request: Json<ChangeRequest>,
     r: HttpRequest,
 ) -> SiteResponse {
     let conn = database.get()?;
-    let option = get_user_by_header(r.headers(), &conn)?;
-    if option.is_none() {
-        return unauthorized();
-    }
-    let user = option.unwrap();
     let result = crate::user::utils::hash(request.0.value)?;
     crate::user::action::update_password(&user.id, result, &conn)?;
+    // Anyone who got in with the old password is logged out
+    delete_other_auth_tokens(&user.id, &user.session.id, &conn)?;
+    audit(&user.username, &user.username, AuditAction::PasswordChange, None, None, &conn)?;
     APIResponse::new(true, Some(true)).respond(&r)
 }
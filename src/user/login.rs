@@ -1,26 +1,65 @@
-use actix_web::{get, post, web::Json, HttpRequest};
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::http::header::LOCATION;
+use actix_web::{get, post, web::Json, HttpRequest, HttpResponse};
 use argon2::{Argon2, PasswordHash, PasswordVerifier};
 
 use crate::api_response::{APIResponse, SiteResponse};
-use crate::error::response::unauthorized;
-use crate::{Database, RedditClient};
+use crate::error::internal_error::InternalError;
+use crate::audit::models::AuditAction;
+use crate::audit::utils::audit;
+use crate::error::response::{bad_request, not_found, too_many_requests, unauthorized};
+use crate::reddit::oauth::{RedditOAuth, STATE_LIFETIME};
+use crate::settings::utils::get_setting_value;
+use crate::{Database, OAuthData, RateLimit, RedditClient, TitleData};
+use diesel::MysqlConnection;
 use serde::{Deserialize, Serialize};
 
-use crate::user::action::{delete_otp, get_opt, get_user_by_id, get_user_by_name};
-use crate::user::models::Status;
-use crate::user::utils::{create_token, generate_otp, get_user_by_header};
+use crate::user::action::{delete_otp, delete_otp_attempts, get_otps, get_user_by_name};
+use crate::user::authentication::{Authenticated, RequireSession};
+use crate::user::models::{AuthToken, Status, User};
+use crate::user::rate_limit::LoginAction;
+use crate::user::role::action::{get_user_permissions, get_user_roles};
+use crate::user::role::models::{Permission, Permissions};
+use crate::user::utils::{
+    add_otp_failure, client_ip, create_token, generate_otp, otp_locked_out, quick_add, user_agent,
+    verify_otp,
+};
 use crate::utils::send_login;
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Me {
+    #[serde(flatten)]
+    pub user: User,
+    //The names of the roles assigned to the user
+    pub roles: Vec<String>,
+    pub permissions: Permissions,
+}
+
 #[get("/api/me")]
-pub async fn me(database: Database, request: HttpRequest) -> SiteResponse {
+pub async fn me(
+    database: Database,
+    user: Authenticated<RequireSession>,
+    request: HttpRequest,
+) -> SiteResponse {
     let connection = database.get()?;
+    let roles = get_user_roles(&user.id, &connection)?
+        .into_iter()
+        .map(|role| role.name)
+        .collect();
+    let me = Me {
+        roles,
+        permissions: user.permissions,
+        user: user.user,
+    };
+    APIResponse::respond_new(Some(me), &request)
+}
 
-    let user = get_user_by_header(request.headers(), &connection)?;
-    if user.is_none() {
-        return unauthorized();
+/// Only Approved users with the login permission can log in
+fn can_login(user: &User, connection: &MysqlConnection) -> Result<bool, InternalError> {
+    if user.status != Status::Approved {
+        return Ok(false);
     }
-
-    APIResponse::respond_new(user, &request)
+    Ok(get_user_permissions(&user.id, connection)?.contains(&Permission::Login))
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -30,14 +69,23 @@ pub struct Login {
 }
 
 #[post("/api/login/password")]
-pub async fn login(login: Json<Login>, database: Database, request: HttpRequest) -> SiteResponse {
+pub async fn login(
+    login: Json<Login>,
+    database: Database,
+    rate_limit: RateLimit,
+    request: HttpRequest,
+) -> SiteResponse {
     let connection = database.get()?;
+    let ip = client_ip(&request);
+    if !rate_limit.allow(LoginAction::Password, &ip, &login.username, &connection)? {
+        return too_many_requests();
+    }
     let option = get_user_by_name(&login.username, &connection)?;
     if option.is_none() {
         return unauthorized();
     }
     let user = option.unwrap();
-    if user.status != Status::Approved || !user.permissions.login {
+    if !can_login(&user, &connection)? {
         return unauthorized();
     }
 
@@ -48,7 +96,7 @@ pub async fn login(login: Json<Login>, database: Database, request: HttpRequest)
     if x.is_err() {
         return unauthorized();
     }
-    let x = create_token(&user, &connection)?;
+    let x = create_token(&user, user_agent(request.headers()), &connection)?;
     return APIResponse::new(true, Some(x)).respond(&request);
 }
 
@@ -62,15 +110,20 @@ pub async fn one_time_password_create(
     otp_request: Json<CreateOTP>,
     reddit_client: RedditClient,
     database: Database,
+    rate_limit: RateLimit,
     request: HttpRequest,
 ) -> SiteResponse {
     let connection = database.get()?;
+    let ip = client_ip(&request);
+    if !rate_limit.allow(LoginAction::OTPCreate, &ip, &otp_request.username, &connection)? {
+        return too_many_requests();
+    }
     let option = get_user_by_name(&otp_request.username, &connection)?;
     if option.is_none() {
         return unauthorized();
     }
     let user = option.unwrap();
-    if user.status != Status::Approved || !user.permissions.login {
+    if !can_login(&user, &connection)? {
         return unauthorized();
     }
     let string = generate_otp(&user.id, &connection)?;
@@ -93,25 +146,273 @@ pub struct UseOTP {
 pub async fn one_time_password(
     otp: Json<UseOTP>,
     database: Database,
+    rate_limit: RateLimit,
     request: HttpRequest,
 ) -> SiteResponse {
     let connection = database.get()?;
-    let option = get_opt(&otp.otp, &connection)?;
-    if option.is_none() {
-        return unauthorized();
+    let ip = client_ip(&request);
+    if !rate_limit.allow(LoginAction::OTP, &ip, &otp.username, &connection)? {
+        return too_many_requests();
+    }
+    let user = get_user_by_name(&otp.username, &connection)?;
+    let id = user.as_ref().map(|user| user.id).unwrap_or(0);
+    if otp_locked_out(&id, &ip, &connection)? {
+        return too_many_requests();
     }
-    let option = option.unwrap();
-    let user = get_user_by_id(&option.user, &connection)?;
     if user.is_none() {
-        //Ask questions later???
+        add_otp_failure(&id, &ip, &connection)?;
         return unauthorized();
     }
     let user = user.unwrap();
-    if user.status != Status::Approved || !user.permissions.login {
-        //Ask questions later???
+    let mut found = None;
+    for value in get_otps(&user.id, &connection)? {
+        if verify_otp(&value, &otp.otp)? {
+            found = Some(value);
+            break;
+        }
+    }
+    if found.is_none() {
+        add_otp_failure(&id, &ip, &connection)?;
+        return unauthorized();
+    }
+    // Only one request gets to use it
+    if delete_otp(found.unwrap().id, &connection)? == 0 {
+        return unauthorized();
+    }
+    if !can_login(&user, &connection)? {
         return unauthorized();
     }
-    delete_otp(option.id, &connection)?;
-    let x = create_token(&user, &connection)?;
+    delete_otp_attempts(&user.id, &connection)?;
+    let x = create_token(&user, user_agent(request.headers()), &connection)?;
     APIResponse::new(true, Some(x)).respond(&request)
 }
+
+/// Ties the Reddit login to the browser that started it
+const STATE_COOKIE: &str = "reddit_state";
+
+fn state_cookie(state: String, oauth: &RedditOAuth) -> Cookie<'static> {
+    Cookie::build(STATE_COOKIE, state)
+        .path("/api/login/reddit")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .secure(oauth.redirect_uri.starts_with("https://"))
+        .max_age(time::Duration::seconds(STATE_LIFETIME))
+        .finish()
+}
+
+/// Sends the user to Reddit to log in. This is the link in the login message
+#[get("/api/login/reddit")]
+pub async fn reddit_login(
+    oauth: OAuthData,
+    database: Database,
+    rate_limit: RateLimit,
+    request: HttpRequest,
+) -> SiteResponse {
+    let oauth = match oauth.get_ref() {
+        Some(oauth) => oauth,
+        None => return not_found(),
+    };
+    let connection = database.get()?;
+    if !rate_limit.allow_ip(&client_ip(&request), &connection)? {
+        return too_many_requests();
+    }
+    let state = RedditOAuth::new_state();
+    Ok(HttpResponse::Found()
+        .insert_header((LOCATION, oauth.authorize(&state)?))
+        .cookie(state_cookie(state, oauth))
+        .finish())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RedditCallback {
+    pub code: String,
+    pub state: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RedditLogin {
+    //Only set if the user is allowed to log in
+    pub token: Option<AuthToken>,
+    pub status: Status,
+    //If this login added the user to the review queue
+    pub submitted: bool,
+}
+
+/// What a Reddit login does for the account
+#[derive(Debug, PartialEq)]
+enum RedditOutcome {
+    //Unknown and the site does not submit users that log in
+    Reject,
+    //Unknown and added to the review queue
+    Submit,
+    //Allowed to log in
+    Session,
+    //Known but not allowed to log in. Only the status is returned
+    Status,
+}
+
+/// With `found_session` accounts still in the review queue get a session.
+/// It only reaches the routes that take any session, their roles do not count until they are approved
+fn reddit_outcome(
+    status: Option<&Status>,
+    submit_unknown: bool,
+    can_login: bool,
+    found_session: bool,
+) -> RedditOutcome {
+    match status {
+        None if submit_unknown => RedditOutcome::Submit,
+        None => RedditOutcome::Reject,
+        Some(_) if can_login => RedditOutcome::Session,
+        Some(Status::Found) if found_session => RedditOutcome::Session,
+        Some(_) => RedditOutcome::Status,
+    }
+}
+
+/// Called by the page Reddit sends the user back to
+#[post("/api/login/reddit")]
+pub async fn reddit_callback(
+    callback: Json<RedditCallback>,
+    oauth: OAuthData,
+    database: Database,
+    titles: TitleData,
+    request: HttpRequest,
+) -> SiteResponse {
+    let oauth = match oauth.get_ref() {
+        Some(oauth) => oauth,
+        None => return not_found(),
+    };
+    let started_here = request
+        .cookie(STATE_COOKIE)
+        .map(|cookie| !callback.state.is_empty() && cookie.value() == callback.state)
+        .unwrap_or(false);
+    if !started_here {
+        return bad_request("The login expired. Try again");
+    }
+    let username = oauth.identify(&callback.code).await?;
+    if username.is_none() {
+        return unauthorized();
+    }
+    let username = username.unwrap();
+    let connection = database.get()?;
+    let user = get_user_by_name(&username, &connection)?;
+    let allowed = match &user {
+        Some(user) => can_login(user, &connection)?,
+        None => false,
+    };
+    let submit = get_setting_value::<bool>("login.reddit.submit", &connection)?;
+    let found_session = get_setting_value::<bool>("login.reddit.found_session", &connection)?;
+    let status = user.as_ref().map(|user| &user.status);
+    let login = match reddit_outcome(status, submit, allowed, found_session) {
+        RedditOutcome::Reject => return unauthorized(),
+        RedditOutcome::Submit => {
+            let submitted = quick_add(&username, &username, &connection, &titles.get())?;
+            if submitted {
+                audit(&username, &username, AuditAction::Submit, None, None, &connection)?;
+            }
+            RedditLogin {
+                token: None,
+                status: Status::Found,
+                submitted,
+            }
+        }
+        RedditOutcome::Session => {
+            let user = user.unwrap();
+            RedditLogin {
+                token: Some(create_token(&user, user_agent(request.headers()), &connection)?),
+                status: user.status,
+                submitted: false,
+            }
+        }
+        RedditOutcome::Status => RedditLogin {
+            token: None,
+            status: user.unwrap().status,
+            submitted: false,
+        },
+    };
+    let mut response = APIResponse::respond_new(Some(login), &request)?;
+    response
+        .add_removal_cookie(&state_cookie(String::new(), oauth))
+        .map_err(actix_web::Error::from)?;
+    Ok(response)
+}
+
+#[test]
+fn reddit_outcome_test() {
+    assert_eq!(reddit_outcome(None, false, false, true), RedditOutcome::Reject);
+    assert_eq!(reddit_outcome(None, true, false, false), RedditOutcome::Submit);
+    assert_eq!(
+        reddit_outcome(Some(&Status::Approved), true, true, false),
+        RedditOutcome::Session
+    );
+    assert_eq!(
+        reddit_outcome(Some(&Status::Found), true, false, false),
+        RedditOutcome::Status
+    );
+    assert_eq!(
+        reddit_outcome(Some(&Status::Found), false, false, true),
+        RedditOutcome::Session
+    );
+    assert_eq!(
+        reddit_outcome(Some(&Status::Denied), false, false, true),
+        RedditOutcome::Status
+    );
+}
+
+#[actix_web::test]
+async fn reddit_callback_test() {
+    use crate::reddit::stand_in;
+    use crate::user::title::catalogue::TitleCatalogue;
+    use actix_web::http::StatusCode;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::web::Data;
+    use actix_web::App;
+    use diesel::r2d2::ConnectionManager;
+
+    let oauth = stand_in::oauth(&stand_in::start());
+    // Never connects. Every request here is turned away before the database is used
+    let pool = r2d2::Pool::builder()
+        .build_unchecked(ConnectionManager::<MysqlConnection>::new("mysql://localhost/test"));
+    let pool = Data::new(pool);
+    let titles =
+        Data::new(TitleCatalogue::load("missing.json".to_string(), "missing.json".into()).await);
+    let app = init_service(
+        App::new()
+            .app_data(pool.clone())
+            .app_data(titles.clone())
+            .app_data(Data::new(Some(oauth)))
+            .service(reddit_callback),
+    )
+    .await;
+    let callback = |code: &str, state: &str| {
+        TestRequest::post()
+            .uri("/api/login/reddit")
+            .set_json(&RedditCallback {
+                code: code.to_string(),
+                state: state.to_string(),
+            })
+    };
+
+    // The state was not handed to this browser
+    let response = call_service(&app, callback(stand_in::GOOD_CODE, "state").to_request()).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let request = callback(stand_in::GOOD_CODE, "state")
+        .cookie(Cookie::new(STATE_COOKIE, "other"))
+        .to_request();
+    assert_eq!(call_service(&app, request).await.status(), StatusCode::BAD_REQUEST);
+    // Reddit rejects the code
+    let request = callback("used", "state")
+        .cookie(Cookie::new(STATE_COOKIE, "state"))
+        .to_request();
+    assert_eq!(call_service(&app, request).await.status(), StatusCode::UNAUTHORIZED);
+
+    let disabled = init_service(
+        App::new()
+            .app_data(pool)
+            .app_data(titles)
+            .app_data(Data::new(None::<RedditOAuth>))
+            .service(reddit_callback),
+    )
+    .await;
+    let response = call_service(&disabled, callback("used", "state").to_request()).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -1,7 +1,12 @@
 pub mod action;
+pub mod authentication;
 mod controllers;
 mod login;
 pub mod models;
+pub mod purge;
+pub mod rate_limit;
+pub mod role;
+mod session_controllers;
 mod team_controllers;
 pub mod title;
 pub mod utils;
@@ -15,7 +20,13 @@ pub fn init(cfg: &mut web::ServiceConfig) {
     cfg.service(login::login)
         .service(login::me)
         .service(login::one_time_password)
-        .service(login::one_time_password_create);
+        .service(login::one_time_password_create)
+        .service(login::reddit_login)
+        .service(login::reddit_callback);
+    debug!("Loading Session Controllers");
+    cfg.service(session_controllers::logout)
+        .service(session_controllers::get_sessions)
+        .service(session_controllers::delete_session);
     debug!("Loading User Controllers");
     cfg.service(change_property)
         .service(submit_user)
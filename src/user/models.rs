@@ -1,5 +1,5 @@
 use crate::schema::*;
-use crate::utils::is_valid;
+use crate::user::title::matcher::{best_title, NO_TITLE};
 use crate::{utils, Titles, get_current_time};
 use diesel::backend::Backend;
 use diesel::deserialize::FromSql;
@@ -19,8 +19,40 @@ use strum_macros::EnumString;
 pub struct AuthToken {
     pub id: i64,
     pub user: i64,
+    //The start of the token. Used to find it
+    pub selector: String,
+    //The keyed hash of the rest of the token
     pub token: String,
     pub created: i64,
+    //Pushed back every time the token is used. Capped by the maximum lifetime
+    pub expiration: i64,
+    pub last_used: i64,
+    pub user_agent: Option<String>,
+}
+
+/// A logged in session as shown to its user. The token itself is never sent back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub id: i64,
+    pub created: i64,
+    pub expiration: i64,
+    pub last_used: i64,
+    pub user_agent: Option<String>,
+    //If this is the session making the request
+    pub current: bool,
+}
+
+impl Session {
+    pub fn new(token: AuthToken, current: &i64) -> Session {
+        Session {
+            current: token.id == *current,
+            id: token.id,
+            created: token.created,
+            expiration: token.expiration,
+            last_used: token.last_used,
+            user_agent: token.user_agent,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
@@ -33,6 +65,17 @@ pub struct OTP {
     pub created: i64,
 }
 
+/// A failed one time password login
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "otp_attempts"]
+pub struct OTPAttempt {
+    pub id: i64,
+    //0 if the username did not exist
+    pub user: i64,
+    pub ip: String,
+    pub created: i64,
+}
+
 #[derive(AsExpression, Debug, Deserialize, Serialize, FromSqlRow, Clone)]
 #[sql_type = "Text"]
 pub struct UserProperties {
@@ -40,21 +83,6 @@ pub struct UserProperties {
     pub description: Option<String>,
 }
 
-#[derive(AsExpression, Debug, Deserialize, Serialize, FromSqlRow, Clone)]
-#[sql_type = "Text"]
-pub struct UserPermissions {
-    #[serde(default)]
-    pub admin: bool,
-    #[serde(default)]
-    pub moderator: bool,
-    #[serde(default)]
-    pub submit: bool,
-    #[serde(default)]
-    pub review_user: bool,
-    #[serde(default)]
-    pub login: bool,
-}
-
 impl UserProperties {
     pub fn set_avatar(&mut self, avatar: String) {
         self.avatar = Some(avatar);
@@ -89,12 +117,10 @@ impl ToSql<Text, Mysql> for UserProperties {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackupUser {
     pub username: String,
-    //USER, MODERATOR, ADMIN
-    pub permissions: UserPermissions,
-    //FOUND, DENIED, APPROVED, BANNED
+    //FOUND, DENIED, APPROVED, BANNED, REMOVED, PENDING_SECOND_REVIEW
     pub status: Status,
     //When was their status changed from FOUND to DENIED or APPROVED
     pub status_changed: i64,
@@ -108,6 +134,30 @@ pub struct BackupUser {
     pub title: String,
     pub birthday: Option<String>,
     pub created: i64,
+    //If a Moderator set the title by hand
+    #[serde(default)]
+    pub title_manual: bool,
+    //The names of the roles assigned to the user. Backups made before roles do not have them
+    #[serde(default)]
+    pub roles: Option<Vec<String>>,
+}
+
+impl BackupUser {
+    pub fn new(user: User, roles: Vec<String>) -> BackupUser {
+        BackupUser {
+            username: user.username,
+            status: user.status,
+            status_changed: user.status_changed,
+            discoverer: user.discoverer,
+            reviewer: user.reviewer,
+            properties: user.properties,
+            title: user.title,
+            birthday: user.birthday,
+            created: user.created,
+            title_manual: user.title_manual,
+            roles: Some(roles),
+        }
+    }
 }
 
 impl Into<User> for BackupUser {
@@ -118,7 +168,6 @@ impl Into<User> for BackupUser {
             username: self.username,
             password: "".to_string(),
             password_changed: 0,
-            permissions: self.permissions,
             status: self.status,
             status_changed: self.status_changed,
             discoverer: self.discoverer,
@@ -127,6 +176,7 @@ impl Into<User> for BackupUser {
             title: self.title,
             birthday: self.birthday,
             created: self.created,
+            title_manual: self.title_manual,
         }
     }
 }
@@ -142,9 +192,7 @@ pub struct User {
     pub password: String,
     #[serde(skip_serializing)]
     pub password_changed: i64,
-    //USER, MODERATOR, ADMIN
-    pub permissions: UserPermissions,
-    //FOUND, DENIED, APPROVED, BANNED
+    //FOUND, DENIED, APPROVED, BANNED, REMOVED, PENDING_SECOND_REVIEW
     pub status: Status,
     //When was their status changed from FOUND to DENIED or APPROVED
     pub status_changed: i64,
@@ -158,12 +206,14 @@ pub struct User {
     pub title: String,
     pub birthday: Option<String>,
     pub created: i64,
+    //If a Moderator set the title by hand. The title is then left alone when the titles are reapplied
+    pub title_manual: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SubmitUser {
     pub username: String,
-    //FOUND, DENIED, APPROVED, BANNED
+    //FOUND, DENIED, APPROVED, BANNED, REMOVED, PENDING_SECOND_REVIEW
     pub status: Option<Status>,
     //The Moderator who approved them or denied them. If the user was banned it will still be set to who approved them
     pub moderator: Option<String>,
@@ -224,22 +274,18 @@ pub enum Status {
     Found,
     Denied,
     Approved,
+    //Removed from the subreddit for breaking the rules
+    Banned,
+    //Removed from the subreddit for any other reason
+    Removed,
+    //Approved by one Moderator and waiting for a different one to confirm it
+    PendingSecondReview,
 }
 
-impl ToSql<Text, Mysql> for UserPermissions {
-    fn to_sql<W: Write>(&self, out: &mut Output<W, Mysql>) -> serialize::Result {
-        let s = serde_json::to_string(self)?;
-        <String as ToSql<Text, Mysql>>::to_sql(&s, out)
-    }
-}
-
-impl FromSql<Text, Mysql> for UserPermissions {
-    fn from_sql(
-        bytes: Option<&<diesel::mysql::Mysql as Backend>::RawValue>,
-    ) -> deserialize::Result<UserPermissions> {
-        let t = <String as FromSql<Text, Mysql>>::from_sql(bytes)?;
-        let result = serde_json::from_str(&t)?;
-        Ok(result)
+impl Status {
+    /// If the status takes the user out of the subreddit
+    pub fn is_removal(&self) -> bool {
+        matches!(self, Status::Banned | Status::Removed)
     }
 }
 
@@ -300,18 +346,11 @@ impl User {
             discoverer,
             reviewer: sub.moderator.unwrap_or_else(default_moderator),
             properties,
-            title: is_valid(&sub.username, titles)
-                .unwrap_or_else(|| "No Title Identified".to_string()),
+            title: best_title(&sub.username, titles).unwrap_or_else(|| NO_TITLE.to_string()),
             birthday: None,
             created: sub.created.unwrap_or_else(utils::get_current_time),
-            permissions: UserPermissions {
-                admin: false,
-                moderator: false,
-                submit: true,
-                review_user: false,
-                login: true,
-            },
             password_changed: get_current_time(),
+            title_manual: false,
         }
     }
     pub fn set_status(&mut self, status: Status) {
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use diesel::MysqlConnection;
+use log::{error, info};
+
+use crate::error::internal_error::InternalError;
+use crate::settings::utils::get_setting_value;
+use crate::user::action::{
+    delete_expired_auth_tokens, delete_expired_otps, delete_old_otp_attempts,
+};
+use crate::utils::get_current_time;
+use crate::DbPool;
+
+/// How often expired one time passwords, tokens and old attempts are removed. In seconds
+const PURGE_INTERVAL: u64 = 3600;
+
+/// Removes expired one time passwords and tokens along with attempts past the lockout
+pub async fn purge_loop(pool: DbPool) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(PURGE_INTERVAL)).await;
+        let conn = match pool.get() {
+            Ok(conn) => conn,
+            Err(error) => {
+                error!("Unable to Purge {}", error);
+                continue;
+            }
+        };
+        if let Err(error) = purge(&conn) {
+            error!("Unable to Purge {:?}", error);
+        }
+    }
+}
+
+fn purge(conn: &MysqlConnection) -> Result<(), InternalError> {
+    let lockout = get_setting_value::<i64>("security.otp.lockout", conn)?;
+    let otps = delete_expired_otps(conn)?;
+    let attempts = delete_old_otp_attempts(get_current_time() - lockout * 1000, conn)?;
+    let tokens = delete_expired_auth_tokens(conn)?;
+    if otps + attempts + tokens > 0 {
+        info!(
+            "Purged {} one time passwords, {} attempts and {} tokens",
+            otps, attempts, tokens
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,201 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use diesel::MysqlConnection;
+
+use crate::error::internal_error::InternalError;
+use crate::settings::utils::get_setting_value;
+use crate::utils::get_current_time;
+
+/// The most keys tracked at once. The oldest key is dropped to make room for a new one
+const MAX_KEYS: usize = 10_000;
+
+/// The login endpoints that are rate limited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginAction {
+    Password,
+    OTP,
+    OTPCreate,
+}
+
+impl LoginAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LoginAction::Password => "password",
+            LoginAction::OTP => "otp",
+            LoginAction::OTPCreate => "otp_create",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Bucket {
+    window_start: i64,
+    count: u64,
+    //Requests are rejected until this time
+    blocked_until: i64,
+}
+
+#[derive(Debug, Default)]
+struct Buckets {
+    buckets: HashMap<String, Bucket>,
+    //The keys in the order they were added
+    order: VecDeque<String>,
+}
+
+impl Buckets {
+    /// The oldest key whose window is over. Otherwise the oldest key that is not blocked.
+    /// Blocked keys are never dropped so going over the limit can't reset them
+    fn evictable(&self, window: i64, now: i64) -> Option<usize> {
+        let bucket = |key: &String| self.buckets.get(key).cloned().unwrap_or_default();
+        self.order
+            .iter()
+            .position(|key| {
+                let bucket = bucket(key);
+                bucket.blocked_until <= now && bucket.window_start + window <= now
+            })
+            .or_else(|| {
+                self.order
+                    .iter()
+                    .position(|key| bucket(key).blocked_until <= now)
+            })
+    }
+}
+
+/// Counts login requests in memory by IP and by username.
+/// Going over the limit in a window blocks the key for the cooldown
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    /// Checks the IP and the username against the limits in the settings.
+    /// Returns false if the request should be rejected
+    pub fn allow(
+        &self,
+        action: LoginAction,
+        ip: &str,
+        username: &str,
+        conn: &MysqlConnection,
+    ) -> Result<bool, InternalError> {
+        let window = get_setting_value::<i64>("security.rate_limit.window", conn)? * 1000;
+        let cooldown = get_setting_value::<i64>("security.rate_limit.cooldown", conn)? * 1000;
+        let ip_max = get_setting_value::<u64>("security.rate_limit.ip", conn)?;
+        let user_max =
+            get_setting_value::<u64>(&format!("security.rate_limit.{}", action.name()), conn)?;
+        let now = get_current_time();
+        // The IP is shared between all the login endpoints
+        if !self.hit(&format!("ip:{}", ip), ip_max, window, cooldown, now) {
+            // A blocked IP can't add username keys to push its own key out
+            return Ok(false);
+        }
+        Ok(self.hit(
+            &format!("{}:{}", action.name(), username.to_lowercase()),
+            user_max,
+            window,
+            cooldown,
+            now,
+        ))
+    }
+
+    /// Only checks the IP. For the login endpoints that do not take a username
+    pub fn allow_ip(&self, ip: &str, conn: &MysqlConnection) -> Result<bool, InternalError> {
+        let window = get_setting_value::<i64>("security.rate_limit.window", conn)? * 1000;
+        let cooldown = get_setting_value::<i64>("security.rate_limit.cooldown", conn)? * 1000;
+        let ip_max = get_setting_value::<u64>("security.rate_limit.ip", conn)?;
+        let now = get_current_time();
+        Ok(self.hit(&format!("ip:{}", ip), ip_max, window, cooldown, now))
+    }
+
+    fn hit(&self, key: &str, max: u64, window: i64, cooldown: i64, now: i64) -> bool {
+        let mut guard = self.buckets.lock().unwrap();
+        let buckets = &mut *guard;
+        if !buckets.buckets.contains_key(key) {
+            if buckets.order.len() >= MAX_KEYS {
+                match buckets.evictable(window, now) {
+                    Some(position) => {
+                        if let Some(oldest) = buckets.order.remove(position) {
+                            buckets.buckets.remove(&oldest);
+                        }
+                    }
+                    // Every key is blocked. New keys are turned away until one can be dropped
+                    None => return false,
+                }
+            }
+            buckets.order.push_back(key.to_string());
+        }
+        let bucket = buckets.buckets.entry(key.to_string()).or_default();
+        if bucket.blocked_until > now {
+            return false;
+        }
+        if bucket.window_start + window <= now {
+            bucket.window_start = now;
+            bucket.count = 0;
+        }
+        bucket.count += 1;
+        if bucket.count > max {
+            bucket.blocked_until = now + cooldown;
+            return false;
+        }
+        true
+    }
+}
+
+#[test]
+fn rate_limiter_test() {
+    let limiter = RateLimiter::default();
+    assert!(limiter.hit("ip:127.0.0.1", 2, 1000, 5000, 0));
+    assert!(limiter.hit("ip:127.0.0.1", 2, 1000, 5000, 10));
+    assert!(!limiter.hit("ip:127.0.0.1", 2, 1000, 5000, 20));
+    // Still in the cooldown after the window is over
+    assert!(!limiter.hit("ip:127.0.0.1", 2, 1000, 5000, 2000));
+    assert!(limiter.hit("ip:127.0.0.1", 2, 1000, 5000, 5020));
+    assert!(limiter.hit("ip:127.0.0.2", 2, 1000, 5000, 20));
+}
+
+#[test]
+fn rate_limiter_bounded_test() {
+    let limiter = RateLimiter::default();
+    for ip in 0..MAX_KEYS + 100 {
+        assert!(limiter.hit(&format!("ip:{}", ip), 2, 1000, 5000, 0));
+    }
+    let buckets = limiter.buckets.lock().unwrap();
+    assert_eq!(buckets.buckets.len(), MAX_KEYS);
+    assert_eq!(buckets.order.len(), MAX_KEYS);
+    // The oldest keys made room for the new ones
+    assert!(!buckets.buckets.contains_key("ip:0"));
+    assert!(buckets
+        .buckets
+        .contains_key(&format!("ip:{}", MAX_KEYS + 99)));
+}
+
+#[test]
+fn rate_limiter_keeps_blocked_test() {
+    let limiter = RateLimiter::default();
+    assert!(!limiter.hit("ip:blocked", 0, 1000, 5000, 0));
+    for ip in 0..MAX_KEYS + 100 {
+        limiter.hit(&format!("ip:{}", ip), 2, 1000, 5000, 10);
+    }
+    // The blocked key is still there so the cooldown can't be skipped
+    assert!(!limiter.hit("ip:blocked", 2, 1000, 5000, 20));
+    assert!(limiter
+        .buckets
+        .lock()
+        .unwrap()
+        .buckets
+        .contains_key("ip:blocked"));
+
+    // Nothing can be dropped when every key is blocked
+    let limiter = RateLimiter::default();
+    for ip in 0..MAX_KEYS {
+        assert!(!limiter.hit(&format!("ip:{}", ip), 0, 1000, 5000, 0));
+    }
+    assert!(!limiter.hit("ip:new", 2, 1000, 5000, 10));
+    assert!(!limiter
+        .buckets
+        .lock()
+        .unwrap()
+        .buckets
+        .contains_key("ip:new"));
+}
@@ -0,0 +1,129 @@
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use diesel::MysqlConnection;
+
+use crate::user::role::models::{Permissions, Role, UserRole};
+use crate::utils::get_current_time;
+
+pub fn get_roles(conn: &MysqlConnection) -> Result<Vec<Role>, DieselError> {
+    use crate::schema::roles::dsl::*;
+    roles.order(id.asc()).load::<Role>(conn)
+}
+
+pub fn get_role(r_id: &i64, conn: &MysqlConnection) -> Result<Option<Role>, DieselError> {
+    use crate::schema::roles::dsl::*;
+    roles.filter(id.eq(r_id)).first::<Role>(conn).optional()
+}
+
+pub fn get_role_by_name(r_name: &str, conn: &MysqlConnection) -> Result<Option<Role>, DieselError> {
+    use crate::schema::roles::dsl::*;
+    roles.filter(name.eq(r_name)).first::<Role>(conn).optional()
+}
+
+pub fn add_role(role: &Role, conn: &MysqlConnection) -> Result<(), DieselError> {
+    use crate::schema::roles::dsl::*;
+    diesel::insert_into(roles).values(role).execute(conn)?;
+    Ok(())
+}
+
+/// Updates everything but the created time
+pub fn update_role(role: &Role, conn: &MysqlConnection) -> Result<(), DieselError> {
+    use crate::schema::roles::dsl::*;
+    diesel::update(roles.filter(id.eq(role.id)))
+        .set((
+            name.eq(&role.name),
+            permissions.eq(&role.permissions),
+            is_default.eq(role.is_default),
+        ))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Deletes the role and takes it away from everyone holding it
+pub fn delete_role(r_id: &i64, conn: &MysqlConnection) -> Result<(), DieselError> {
+    use crate::schema::{roles, user_roles};
+    conn.transaction(|| {
+        diesel::delete(user_roles::table.filter(user_roles::role.eq(r_id))).execute(conn)?;
+        diesel::delete(roles::table.filter(roles::id.eq(r_id))).execute(conn)?;
+        Ok(())
+    })
+}
+
+/// The roles assigned to the user
+pub fn get_user_roles(u: &i64, conn: &MysqlConnection) -> Result<Vec<Role>, DieselError> {
+    use crate::schema::{roles, user_roles};
+    let assigned = user_roles::table
+        .select(user_roles::role)
+        .filter(user_roles::user.eq(u));
+    roles::table
+        .filter(roles::id.eq_any(assigned))
+        .order(roles::id.asc())
+        .load::<Role>(conn)
+}
+
+pub fn has_user_role(u: &i64, r: &i64, conn: &MysqlConnection) -> Result<bool, DieselError> {
+    use crate::schema::user_roles::dsl::*;
+    let found: Option<UserRole> = user_roles
+        .filter(user.eq(u).and(role.eq(r)))
+        .first(conn)
+        .optional()?;
+    Ok(found.is_some())
+}
+
+/// Every role assignment
+pub fn get_all_user_roles(conn: &MysqlConnection) -> Result<Vec<UserRole>, DieselError> {
+    use crate::schema::user_roles::dsl::*;
+    user_roles.order(id.asc()).load::<UserRole>(conn)
+}
+
+/// Assigns the default roles to a new user
+pub fn add_default_roles(u: &i64, conn: &MysqlConnection) -> Result<(), DieselError> {
+    use crate::schema::{roles, user_roles};
+    let defaults: Vec<i64> = roles::table
+        .select(roles::id)
+        .filter(roles::is_default.eq(true))
+        .load(conn)?;
+    let values: Vec<UserRole> = defaults
+        .into_iter()
+        .map(|r| UserRole {
+            id: 0,
+            user: *u,
+            role: r,
+            created: get_current_time(),
+        })
+        .collect();
+    diesel::insert_into(user_roles::table)
+        .values(&values)
+        .execute(conn)?;
+    Ok(())
+}
+
+pub fn add_user_role(value: &UserRole, conn: &MysqlConnection) -> Result<(), DieselError> {
+    use crate::schema::user_roles::dsl::*;
+    diesel::insert_into(user_roles)
+        .values(value)
+        .execute(conn)?;
+    Ok(())
+}
+
+pub fn delete_user_role(u: &i64, r: &i64, conn: &MysqlConnection) -> Result<usize, DieselError> {
+    use crate::schema::user_roles::dsl::*;
+    diesel::delete(user_roles.filter(user.eq(u).and(role.eq(r)))).execute(conn)
+}
+
+/// Everything the user is allowed to do through their roles
+pub fn get_user_permissions(u: &i64, conn: &MysqlConnection) -> Result<Permissions, DieselError> {
+    use crate::schema::{roles, user_roles};
+    let assigned = user_roles::table
+        .select(user_roles::role)
+        .filter(user_roles::user.eq(u));
+    let values: Vec<Permissions> = roles::table
+        .select(roles::permissions)
+        .filter(roles::id.eq_any(assigned))
+        .load(conn)?;
+    let mut permissions = Permissions::default();
+    for value in values {
+        permissions.0.extend(value.0);
+    }
+    Ok(permissions)
+}
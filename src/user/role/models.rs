@@ -0,0 +1,183 @@
+use std::collections::BTreeSet;
+use std::io::Write;
+
+use diesel::backend::Backend;
+use diesel::deserialize::FromSql;
+use diesel::mysql::Mysql;
+use diesel::serialize::{Output, ToSql};
+use diesel::sql_types::Text;
+use diesel::{deserialize, serialize};
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumIter};
+
+use crate::schema::*;
+
+/// The role created with every permission. Given to the first user on install
+pub const ADMIN_ROLE: &str = "Admin";
+
+/// Something a role lets its users do
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Display,
+    EnumIter,
+)]
+pub enum Permission {
+    //Log into the site
+    #[serde(rename = "login")]
+    #[strum(serialize = "login")]
+    Login,
+    //Submit users to the review queue
+    #[serde(rename = "submit")]
+    #[strum(serialize = "submit")]
+    Submit,
+    #[serde(rename = "review.approve")]
+    #[strum(serialize = "review.approve")]
+    ReviewApprove,
+    #[serde(rename = "review.deny")]
+    #[strum(serialize = "review.deny")]
+    ReviewDeny,
+    //Ban or Remove users from the subreddit
+    #[serde(rename = "review.remove")]
+    #[strum(serialize = "review.remove")]
+    ReviewRemove,
+    //Look up users, their history and stats
+    #[serde(rename = "users.view")]
+    #[strum(serialize = "users.view")]
+    UsersView,
+    #[serde(rename = "users.edit")]
+    #[strum(serialize = "users.edit")]
+    UsersEdit,
+    //Release review claims held by someone else
+    #[serde(rename = "claims.release")]
+    #[strum(serialize = "claims.release")]
+    ClaimsRelease,
+    #[serde(rename = "team.edit")]
+    #[strum(serialize = "team.edit")]
+    TeamEdit,
+    #[serde(rename = "settings.edit")]
+    #[strum(serialize = "settings.edit")]
+    SettingsEdit,
+    #[serde(rename = "titles.edit")]
+    #[strum(serialize = "titles.edit")]
+    TitlesEdit,
+    //Manage roles and who holds them
+    #[serde(rename = "roles.edit")]
+    #[strum(serialize = "roles.edit")]
+    RolesEdit,
+    #[serde(rename = "discovery.run")]
+    #[strum(serialize = "discovery.run")]
+    DiscoveryRun,
+    #[serde(rename = "audit.view")]
+    #[strum(serialize = "audit.view")]
+    AuditView,
+}
+
+/// A set of permissions stored as a JSON array
+#[derive(AsExpression, Debug, Deserialize, Serialize, FromSqlRow, Clone, Default, PartialEq)]
+#[sql_type = "Text"]
+pub struct Permissions(pub BTreeSet<Permission>);
+
+impl Permissions {
+    pub fn contains(&self, permission: &Permission) -> bool {
+        self.0.contains(permission)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "roles"]
+pub struct Role {
+    pub id: i64,
+    pub name: String,
+    pub permissions: Permissions,
+    //Assigned to every new user. They can be taken away like any other role
+    pub is_default: bool,
+    pub created: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "user_roles"]
+pub struct UserRole {
+    pub id: i64,
+    pub user: i64,
+    pub role: i64,
+    pub created: i64,
+}
+
+/// If anyone holding a role could still manage the roles.
+/// Without them the roles can only be fixed in the database
+pub fn has_role_editor(roles: &[Role], assigned: &[UserRole]) -> bool {
+    assigned.iter().any(|value| {
+        roles
+            .iter()
+            .any(|role| role.id == value.role && role.permissions.contains(&Permission::RolesEdit))
+    })
+}
+
+/// A new role or the new values for a role
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleRequest {
+    pub name: String,
+    pub permissions: Permissions,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+impl ToSql<Text, Mysql> for Permissions {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Mysql>) -> serialize::Result {
+        let s = serde_json::to_string(&self.0)?;
+        <String as ToSql<Text, Mysql>>::to_sql(&s, out)
+    }
+}
+
+impl FromSql<Text, Mysql> for Permissions {
+    fn from_sql(
+        bytes: Option<&<diesel::mysql::Mysql as Backend>::RawValue>,
+    ) -> deserialize::Result<Permissions> {
+        let t = <String as FromSql<Text, Mysql>>::from_sql(bytes)?;
+        let result = serde_json::from_str(&t)?;
+        Ok(Permissions(result))
+    }
+}
+
+#[test]
+fn permissions_json_test() {
+    let permissions: Permissions =
+        serde_json::from_str(r#"["review.deny","login","review.approve"]"#).unwrap();
+    assert!(permissions.contains(&Permission::ReviewApprove));
+    assert!(!permissions.contains(&Permission::ReviewRemove));
+    assert_eq!(
+        serde_json::to_string(&permissions).unwrap(),
+        r#"["login","review.approve","review.deny"]"#
+    );
+    assert_eq!(Permission::SettingsEdit.to_string(), "settings.edit");
+}
+
+#[test]
+fn has_role_editor_test() {
+    let role = |id: i64, permissions: &str| Role {
+        id,
+        name: id.to_string(),
+        permissions: serde_json::from_str(permissions).unwrap(),
+        is_default: false,
+        created: 0,
+    };
+    let assigned = |user: i64, role: i64| UserRole {
+        id: 0,
+        user,
+        role,
+        created: 0,
+    };
+    let roles = vec![role(1, r#"["roles.edit"]"#), role(2, r#"["login"]"#)];
+    assert!(has_role_editor(&roles, &[assigned(1, 1), assigned(2, 2)]));
+    assert!(!has_role_editor(&roles, &[assigned(2, 2)]));
+    assert!(!has_role_editor(&roles[1..], &[assigned(1, 1)]));
+}
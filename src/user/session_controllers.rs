@@ -0,0 +1,48 @@
+use actix_web::{delete, get, post, web, HttpRequest};
+
+use crate::api_response::{APIResponse, SiteResponse};
+use crate::error::response::not_found;
+use crate::user::action::{delete_auth_token, get_auth_tokens};
+use crate::user::authentication::{Authenticated, RequireSession};
+use crate::user::models::Session;
+use crate::Database;
+
+/// Ends the session the request was made with
+#[post("/api/logout")]
+pub async fn logout(
+    database: Database,
+    user: Authenticated<RequireSession>,
+    r: HttpRequest,
+) -> SiteResponse {
+    let conn = database.get()?;
+    delete_auth_token(&user.session.id, &user.id, &conn)?;
+    APIResponse::new(true, Some(true)).respond(&r)
+}
+
+#[get("/api/me/sessions")]
+pub async fn get_sessions(
+    database: Database,
+    user: Authenticated<RequireSession>,
+    r: HttpRequest,
+) -> SiteResponse {
+    let conn = database.get()?;
+    let sessions: Vec<Session> = get_auth_tokens(&user.id, &conn)?
+        .into_iter()
+        .map(|token| Session::new(token, &user.session.id))
+        .collect();
+    APIResponse::new(true, Some(sessions)).respond(&r)
+}
+
+#[delete("/api/me/sessions/{id}")]
+pub async fn delete_session(
+    database: Database,
+    user: Authenticated<RequireSession>,
+    path: web::Path<i64>,
+    r: HttpRequest,
+) -> SiteResponse {
+    let conn = database.get()?;
+    if delete_auth_token(&path.into_inner(), &user.id, &conn)? == 0 {
+        return not_found();
+    }
+    APIResponse::new(true, Some(true)).respond(&r)
+}
@@ -2,7 +2,7 @@ use actix_web::HttpRequest;
 use std::collections::HashMap;
 
 use crate::api_response::{APIResponse, SiteResponse};
-use crate::Database;
+use crate::{Database, RedditClient};
 
 use crate::user::action::{get_team_members, get_team_user};
 
@@ -11,7 +11,11 @@ use crate::utils::get_avatar;
 use actix_web::get;
 
 #[get("/team/get")]
-pub async fn get_team(database: Database, req: HttpRequest) -> SiteResponse {
+pub async fn get_team(
+    database: Database,
+    reddit_client: RedditClient,
+    req: HttpRequest,
+) -> SiteResponse {
     let connection = database.get()?;
     let mut response = HashMap::<Level, Vec<TeamResponse>>::new();
     let vec = get_team_members(&connection)?;
@@ -21,7 +25,7 @@ pub async fn get_team(database: Database, req: HttpRequest) -> SiteResponse {
         }
         let team = response.get_mut(&x.level).unwrap();
         let mut user = get_team_user(&x.user, &connection)?.unwrap();
-        let avatar = get_avatar(&user.username, &user.properties).await?;
+        let avatar = get_avatar(&user.username, &user.properties, &reddit_client).await?;
         user.properties.avatar = Some(avatar);
         team.push(TeamResponse {
             user,
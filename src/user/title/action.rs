@@ -0,0 +1,97 @@
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use diesel::MysqlConnection;
+
+use crate::user::title::models::DBTitle;
+use crate::utils::get_current_time;
+
+/// The titles in order. Retired titles are only included if asked for
+pub fn get_titles(with_retired: bool, conn: &MysqlConnection) -> Result<Vec<DBTitle>, DieselError> {
+    use crate::schema::titles::dsl::*;
+    if with_retired {
+        titles
+            .order((ordering.asc(), id.asc()))
+            .load::<DBTitle>(conn)
+    } else {
+        titles
+            .filter(retired.eq(false))
+            .order((ordering.asc(), id.asc()))
+            .load::<DBTitle>(conn)
+    }
+}
+
+pub fn get_title(t_id: &i64, conn: &MysqlConnection) -> Result<Option<DBTitle>, DieselError> {
+    use crate::schema::titles::dsl::*;
+    titles.filter(id.eq(t_id)).first::<DBTitle>(conn).optional()
+}
+
+pub fn get_title_by_value(
+    t_value: &str,
+    conn: &MysqlConnection,
+) -> Result<Option<DBTitle>, DieselError> {
+    use crate::schema::titles::dsl::*;
+    titles
+        .filter(value.eq(t_value))
+        .first::<DBTitle>(conn)
+        .optional()
+}
+
+/// The ordering a new title should get to end up last
+pub fn next_ordering(conn: &MysqlConnection) -> Result<i64, DieselError> {
+    use crate::schema::titles::dsl::*;
+    let last: Option<i64> = titles.select(diesel::dsl::max(ordering)).first(conn)?;
+    Ok(last.map(|x| x + 1).unwrap_or(0))
+}
+
+pub fn add_title(title: &DBTitle, conn: &MysqlConnection) -> Result<(), DieselError> {
+    use crate::schema::titles::dsl::*;
+    diesel::insert_into(titles).values(title).execute(conn)?;
+    Ok(())
+}
+
+/// Updates everything but the ordering, retired and created
+pub fn update_title_element(title: &DBTitle, conn: &MysqlConnection) -> Result<(), DieselError> {
+    use crate::schema::titles::dsl::*;
+    diesel::update(titles.filter(id.eq(title.id)))
+        .set((
+            value.eq(&title.value),
+            proper_name.eq(&title.proper_name),
+            description.eq(&title.description),
+            color.eq(&title.color),
+            possible_titles.eq(&title.possible_titles),
+            priority.eq(&title.priority),
+            exclude.eq(&title.exclude),
+            position.eq(&title.position),
+            word_boundary.eq(&title.word_boundary),
+            leetspeak.eq(&title.leetspeak),
+            edited.eq(title.edited),
+            updated.eq(get_current_time()),
+        ))
+        .execute(conn)?;
+    Ok(())
+}
+
+pub fn set_retired(
+    t_id: &i64,
+    value_retired: bool,
+    conn: &MysqlConnection,
+) -> Result<(), DieselError> {
+    use crate::schema::titles::dsl::*;
+    diesel::update(titles.filter(id.eq(t_id)))
+        .set((retired.eq(value_retired), updated.eq(get_current_time())))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Orders the titles in the order of the ids given
+pub fn set_ordering(ids: &[i64], conn: &MysqlConnection) -> Result<(), DieselError> {
+    use crate::schema::titles::dsl::*;
+    conn.transaction(|| {
+        for (index, t_id) in ids.iter().enumerate() {
+            diesel::update(titles.filter(id.eq(t_id)))
+                .set(ordering.eq(index as i64))
+                .execute(conn)?;
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,258 @@
+use std::fs::{read_to_string, write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use diesel::MysqlConnection;
+use hyper::{Body, Client, Method, Request};
+use hyper_tls::HttpsConnector;
+use jsonschema::JSONSchema;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::models::AuditAction;
+use crate::audit::utils::{audit, SYSTEM};
+use crate::error::internal_error::InternalError;
+use crate::settings::utils::get_setting_value;
+use crate::user::title::action::{
+    add_title, get_title_by_value, get_titles, next_ordering, update_title_element,
+};
+use crate::user::title::models::DBTitle;
+use crate::user::title::{TitleElement, Titles};
+use crate::utils::get_current_time;
+use crate::{DbPool, TitleData};
+
+const SCHEMA: &str = include_str!("../../../titles/titles.schema.json");
+/// Used when the source and the cache are both unavailable
+const BUNDLED: &str = include_str!("../../../titles/titles.json");
+/// How often the reload interval is checked while reloading is turned off. In seconds
+const RELOAD_POLL: u64 = 60;
+
+/// The titles in use. They can be swapped out without restarting the server
+pub struct TitleCatalogue {
+    titles: ArcSwap<Titles>,
+    //A URL or a file path
+    source: String,
+    //Where the last good copy of the titles is kept
+    cache: PathBuf,
+}
+
+impl TitleCatalogue {
+    /// Loads the titles from the source. If that fails the cache is used then the bundled titles
+    pub async fn load(source: String, cache: PathBuf) -> TitleCatalogue {
+        let result = fetch(&source).await.and_then(|content| {
+            let titles = parse(&content)?;
+            Ok((content, titles))
+        });
+        let titles = match result {
+            Ok((content, titles)) => {
+                save_cache(&cache, &content);
+                titles
+            }
+            Err(error) => {
+                error!("Unable to Load Titles File from {}: {:?}", &source, error);
+                load_fallback(&cache)
+            }
+        };
+        info!("Loaded {} Titles", titles.titles.len());
+        TitleCatalogue {
+            titles: ArcSwap::from_pointee(titles),
+            source,
+            cache,
+        }
+    }
+
+    pub fn get(&self) -> Arc<Titles> {
+        self.titles.load_full()
+    }
+
+    /// Downloads the titles again. New titles are added and changed ones are updated unless
+    /// an admin edited them. Nothing changes if the new titles are invalid
+    pub async fn reload(&self, conn: &MysqlConnection) -> Result<TitleImport, InternalError> {
+        let content = fetch(&self.source).await?;
+        let titles = parse(&content)?;
+        save_cache(&self.cache, &content);
+        let report = import_titles(&titles, true, conn)?;
+        self.refresh(conn)?;
+        info!(
+            "Reloaded Titles. {} new, {} updated and {} edited by hand",
+            report.added, report.updated, report.skipped
+        );
+        Ok(report)
+    }
+
+    /// Adds the loaded titles to the database if they are missing then serves the titles from the database.
+    /// Existing titles are not updated because the loaded titles could be an old cache
+    pub fn sync(&self, conn: &MysqlConnection) -> Result<(), InternalError> {
+        let report = import_titles(&self.get(), false, conn)?;
+        if report.added > 0 {
+            info!("Added {} Titles to the Database", report.added);
+        }
+        self.refresh(conn)
+    }
+
+    /// Replaces the titles in use with the active titles in the database
+    pub fn refresh(&self, conn: &MysqlConnection) -> Result<(), InternalError> {
+        let titles = get_titles(false, conn)?
+            .into_iter()
+            .map(TitleElement::from)
+            .collect();
+        self.titles.store(Arc::new(Titles { titles }));
+        Ok(())
+    }
+}
+
+/// What an import did to the titles in the database
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct TitleImport {
+    pub added: usize,
+    pub updated: usize,
+    //Titles that changed but were kept because an admin edited them
+    pub skipped: usize,
+}
+
+impl TitleImport {
+    pub fn changed(&self) -> bool {
+        self.added > 0 || self.updated > 0
+    }
+}
+
+/// Adds every title that is not in the database yet. If update is true titles that changed are
+/// updated too. Titles edited through the admin panel are left alone so the edits are kept
+pub fn import_titles(
+    titles: &Titles,
+    update: bool,
+    conn: &MysqlConnection,
+) -> Result<TitleImport, InternalError> {
+    let mut ordering = next_ordering(conn)?;
+    let mut report = TitleImport::default();
+    for title in &titles.titles {
+        let existing = get_title_by_value(&title.value.to_lowercase(), conn)?;
+        if existing.is_none() {
+            add_title(
+                &DBTitle::new(title.clone(), ordering, get_current_time()),
+                conn,
+            )?;
+            ordering += 1;
+            report.added += 1;
+            continue;
+        }
+        let existing = existing.unwrap();
+        let mut new = DBTitle::new(title.clone(), existing.ordering, existing.created);
+        if !update || TitleElement::from(new.clone()) == TitleElement::from(existing.clone()) {
+            continue;
+        }
+        if existing.edited {
+            report.skipped += 1;
+            continue;
+        }
+        new.id = existing.id;
+        update_title_element(&new, conn)?;
+        report.updated += 1;
+    }
+    Ok(report)
+}
+
+/// Reloads the titles every `titles.reload_interval` seconds. 0 turns it off until the setting is changed
+pub async fn reload_loop(titles: TitleData, pool: DbPool) {
+    loop {
+        let interval = pool
+            .get()
+            .map_err(InternalError::from)
+            .and_then(|conn| get_setting_value::<u64>("titles.reload_interval", &conn))
+            .unwrap_or(3600);
+        if interval == 0 {
+            // Turned off. Checks again later in case the setting is changed
+            tokio::time::sleep(Duration::from_secs(RELOAD_POLL)).await;
+            continue;
+        }
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+        let conn = match pool.get() {
+            Ok(conn) => conn,
+            Err(error) => {
+                error!("Unable to Reload Titles {}", error);
+                continue;
+            }
+        };
+        match titles.reload(&conn).await {
+            Ok(report) if !report.changed() => {}
+            Ok(report) => {
+                let report = serde_json::to_string(&report).ok();
+                if let Err(error) = audit(
+                    SYSTEM,
+                    "titles",
+                    AuditAction::TitleReload,
+                    None,
+                    report,
+                    &conn,
+                ) {
+                    error!("Unable to record the Title Reload {}", error);
+                }
+            }
+            Err(error) => error!("Unable to Reload Titles {:?}", error),
+        }
+    }
+}
+
+async fn fetch(source: &str) -> Result<String, InternalError> {
+    if !source.starts_with("http://") && !source.starts_with("https://") {
+        return Ok(read_to_string(source)?);
+    }
+    let https = HttpsConnector::new();
+    let client = Client::builder().build::<_, hyper::Body>(https);
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(source)
+        .body(Body::empty())
+        .map_err(|error| InternalError::Error(error.to_string()))?;
+    let response = client.request(request).await?;
+    if !response.status().is_success() {
+        return Err(InternalError::Error(format!(
+            "Titles returned {}",
+            response.status()
+        )));
+    }
+    let bytes = hyper::body::to_bytes(response.into_body()).await?;
+    Ok(String::from_utf8(bytes.to_vec())?)
+}
+
+/// Parses the titles after checking them against titles.schema.json
+pub fn parse(content: &str) -> Result<Titles, InternalError> {
+    let value: serde_json::Value = serde_json::from_str(content)?;
+    let schema: serde_json::Value = serde_json::from_str(SCHEMA)?;
+    let schema = JSONSchema::compile(&schema)
+        .map_err(|error| InternalError::Error(format!("Invalid Titles Schema {}", error)))?;
+    if let Err(errors) = schema.validate(&value) {
+        let errors: Vec<String> = errors.map(|error| error.to_string()).collect();
+        return Err(InternalError::Error(format!(
+            "Titles do not match the schema: {}",
+            errors.join(", ")
+        )));
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+fn save_cache(cache: &Path, content: &str) {
+    if let Err(error) = write(cache, content) {
+        warn!("Unable to cache the Titles {}", error);
+    }
+}
+
+fn load_fallback(cache: &Path) -> Titles {
+    if let Ok(content) = read_to_string(cache) {
+        match parse(&content) {
+            Ok(titles) => return titles,
+            Err(error) => error!("Cached Titles are invalid {:?}", error),
+        }
+    }
+    warn!("Using the Bundled Titles");
+    parse(BUNDLED).expect("Bundled Titles are invalid")
+}
+
+#[test]
+fn bundled_titles_test() {
+    let titles = parse(BUNDLED).unwrap();
+    assert!(!titles.titles.is_empty());
+    assert!(parse(r#"{"titles": [{"value": "king"}]}"#).is_err());
+}
@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+
+use crate::user::title::{TitleElement, TitlePosition, Titles};
+
+/// Confidence lost when the title was only found after undoing leetspeak
+const LEETSPEAK_PENALTY: f64 = 0.8;
+
+/// The title given to users where no title was found
+pub const NO_TITLE: &str = "No Title Identified";
+
+/// A title that was found inside a username
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TitleMatch {
+    //The title the user would be given. Can be one of the possible titles
+    pub value: String,
+    //The title element that matched
+    pub title: String,
+    //1.0 if the title is its own word inside the username
+    pub confidence: f64,
+    pub priority: i64,
+}
+
+/// Every title the username could have. The best match is first
+pub fn match_titles(username: &str, titles: &Titles) -> Vec<TitleMatch> {
+    let plain = Candidate::new(username.to_string());
+    let leet = Candidate::new(undo_leetspeak(username));
+    let mut matches: Vec<TitleMatch> = Vec::new();
+    for title in &titles.titles {
+        if plain.excluded(title) || leet.excluded(title) {
+            continue;
+        }
+        let leetspeak = title.leetspeak.unwrap_or(true);
+        let found = plain.find(&title.value, title).or_else(|| {
+            if leetspeak {
+                leet.find(&title.value, title)
+                    .map(|confidence| confidence * LEETSPEAK_PENALTY)
+            } else {
+                None
+            }
+        });
+        let confidence = match found {
+            Some(confidence) => confidence,
+            None => continue,
+        };
+        let priority = title.priority.unwrap_or(0);
+        push_match(&mut matches, &title.value, &title.value, confidence, priority);
+        if let Some(possibles) = &title.possible_titles {
+            for possible in possibles {
+                let found = plain.find(possible, title).or_else(|| {
+                    if leetspeak {
+                        leet.find(possible, title)
+                            .map(|confidence| confidence * LEETSPEAK_PENALTY)
+                    } else {
+                        None
+                    }
+                });
+                if let Some(confidence) = found {
+                    push_match(&mut matches, possible, &title.value, confidence, priority);
+                }
+            }
+        }
+    }
+    matches.sort_by(|a, b| {
+        b.priority
+            .cmp(&a.priority)
+            .then(b.confidence.total_cmp(&a.confidence))
+            .then(b.value.len().cmp(&a.value.len()))
+    });
+    matches
+}
+
+/// The value of the best match
+pub fn best_title(username: &str, titles: &Titles) -> Option<String> {
+    match_titles(username, titles)
+        .into_iter()
+        .next()
+        .map(|title| title.value)
+}
+
+fn push_match(matches: &mut Vec<TitleMatch>, value: &str, title: &str, confidence: f64, priority: i64) {
+    let value = value.to_lowercase();
+    if let Some(existing) = matches.iter_mut().find(|x| x.value.eq(&value)) {
+        if existing.confidence < confidence {
+            existing.confidence = confidence;
+            existing.title = title.to_string();
+            existing.priority = priority;
+        }
+        return;
+    }
+    matches.push(TitleMatch {
+        value,
+        title: title.to_string(),
+        confidence,
+        priority,
+    });
+}
+
+fn undo_leetspeak(username: &str) -> String {
+    username
+        .chars()
+        .map(|c| match c {
+            '0' => 'o',
+            '1' | '!' => 'i',
+            '3' => 'e',
+            '4' | '@' => 'a',
+            '5' | '$' => 's',
+            '7' => 't',
+            '8' => 'b',
+            _ => c,
+        })
+        .collect()
+}
+
+/// A username prepared for matching
+struct Candidate {
+    lower: String,
+    //boundaries[i] is true if a word starts or ends at byte i
+    boundaries: Vec<bool>,
+}
+
+impl Candidate {
+    fn new(username: String) -> Candidate {
+        let bytes = username.as_bytes();
+        let mut boundaries = vec![false; bytes.len() + 1];
+        boundaries[0] = true;
+        boundaries[bytes.len()] = true;
+        for i in 1..bytes.len() {
+            let (before, current) = (bytes[i - 1], bytes[i]);
+            boundaries[i] = !before.is_ascii_alphanumeric()
+                || !current.is_ascii_alphanumeric()
+                || (before.is_ascii_lowercase() && current.is_ascii_uppercase())
+                || (before.is_ascii_digit() != current.is_ascii_digit())
+                || (before.is_ascii_uppercase()
+                    && current.is_ascii_uppercase()
+                    && bytes.get(i + 1).map_or(false, |x| x.is_ascii_lowercase()));
+        }
+        Candidate {
+            lower: username.to_ascii_lowercase(),
+            boundaries,
+        }
+    }
+
+    fn excluded(&self, title: &TitleElement) -> bool {
+        title.exclude.as_ref().map_or(false, |exclude| {
+            exclude
+                .iter()
+                .any(|word| self.lower.contains(&word.to_lowercase()))
+        })
+    }
+
+    /// The confidence of the best place the value can be found following the rules of the title
+    fn find(&self, value: &str, title: &TitleElement) -> Option<f64> {
+        let value = value.to_lowercase();
+        if value.is_empty() {
+            return None;
+        }
+        let position = title.position.clone().unwrap_or(TitlePosition::Anywhere);
+        let word_boundary = title.word_boundary.unwrap_or(false);
+        let mut best = None;
+        for (start, _) in self.lower.match_indices(&value) {
+            let end = start + value.len();
+            match position {
+                TitlePosition::Prefix if start != 0 => continue,
+                TitlePosition::Suffix if end != self.lower.len() => continue,
+                _ => {}
+            }
+            let confidence = match (self.boundaries[start], self.boundaries[end]) {
+                (true, true) => 1.0,
+                (true, false) | (false, true) => 0.7,
+                (false, false) => 0.4,
+            };
+            if word_boundary && confidence < 1.0 {
+                continue;
+            }
+            if best.map_or(true, |x| confidence > x) {
+                best = Some(confidence);
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn titles() -> Titles {
+        serde_json::from_str(include_str!("../../../titles/titles.json")).unwrap()
+    }
+
+    #[test]
+    fn best_title_test() {
+        let titles = titles();
+        assert_eq!(best_title("KingTuxWH", &titles).unwrap(), "king");
+        assert_eq!(best_title("QueenTux", &titles).unwrap(), "queen");
+        assert_eq!(best_title("VikingTux", &titles).unwrap(), "viking");
+        assert_eq!(best_title("LordTux", &titles).unwrap(), "lord");
+        assert_eq!(best_title("CzArTux", &titles).unwrap(), "czar");
+        assert_eq!(best_title("PrincessPeach", &titles).unwrap(), "princess");
+        assert_eq!(best_title("K1ngTux", &titles).unwrap(), "king");
+        assert!(best_title("Kingston_Ontario", &titles).is_none());
+        assert!(best_title("TuxTheLinuxPenguin", &titles).is_none());
+    }
+
+    #[test]
+    fn rules_test() {
+        let mut titles = Titles { titles: vec![] };
+        titles.titles.push(serde_json::from_str(r#"{"value": "earl", "properName": "Earl", "wordBoundary": true}"#).unwrap());
+        titles.titles.push(serde_json::from_str(r#"{"value": "lady", "properName": "Lady", "position": "prefix", "leetspeak": false}"#).unwrap());
+        titles.titles.push(serde_json::from_str(r#"{"value": "duke", "properName": "Duke", "priority": 10}"#).unwrap());
+
+        assert!(match_titles("earlobe", &titles).is_empty());
+        assert_eq!(best_title("EarlGrey", &titles).unwrap(), "earl");
+        assert!(match_titles("TheLady", &titles).is_empty());
+        assert!(match_titles("L4dyTux", &titles).is_empty());
+        assert_eq!(best_title("LadyTheDuke", &titles).unwrap(), "duke");
+
+        let matches = match_titles("LadyTheDuke", &titles);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[1].value, "lady");
+        assert_eq!(matches[1].confidence, 1.0);
+    }
+}
@@ -1,5 +1,13 @@
+pub mod action;
+pub mod catalogue;
+pub mod matcher;
+pub mod models;
+pub mod retitle;
+
+use diesel::sql_types::Text;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
+use strum_macros::{Display, EnumString};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Titles {
@@ -7,7 +15,7 @@ pub struct Titles {
     pub titles: Vec<TitleElement>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct TitleElement {
     #[serde(rename = "description")]
     pub description: Option<String>,
@@ -20,6 +28,42 @@ pub struct TitleElement {
 
     #[serde(rename = "value")]
     pub value: String,
+
+    /// The RGB HTML Color
+    #[serde(rename = "color", skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+
+    /// Titles with a higher priority win over titles with a better match. Defaults to 0
+    #[serde(rename = "priority", skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i64>,
+
+    /// Usernames containing any of these are never given the title
+    #[serde(rename = "exclude", skip_serializing_if = "Option::is_none")]
+    pub exclude: Option<Vec<String>>,
+
+    /// Where in the username the title has to be. Defaults to anywhere
+    #[serde(rename = "position", skip_serializing_if = "Option::is_none")]
+    pub position: Option<TitlePosition>,
+
+    /// Only match the title if it is its own word inside the username
+    #[serde(rename = "wordBoundary", skip_serializing_if = "Option::is_none")]
+    pub word_boundary: Option<bool>,
+
+    /// Also match the title written in leetspeak. Defaults to true
+    #[serde(rename = "leetspeak", skip_serializing_if = "Option::is_none")]
+    pub leetspeak: Option<bool>,
+}
+
+#[derive(
+AsExpression, Serialize, Deserialize, FromSqlRow, Clone, Debug, PartialEq, Display, EnumString,
+)]
+#[sql_type = "Text"]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum TitlePosition {
+    Anywhere,
+    Prefix,
+    Suffix,
 }
 
 impl Display for TitleElement {
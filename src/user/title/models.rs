@@ -0,0 +1,122 @@
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize::FromSql;
+use diesel::mysql::Mysql;
+use diesel::serialize::{Output, ToSql};
+use diesel::sql_types::Text;
+use diesel::{deserialize, serialize};
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::*;
+use crate::user::title::{TitleElement, TitlePosition};
+
+/// A list of strings stored as a JSON array
+#[derive(AsExpression, Debug, Deserialize, Serialize, FromSqlRow, Clone)]
+#[sql_type = "Text"]
+pub struct StringList(pub Vec<String>);
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "titles"]
+pub struct DBTitle {
+    pub id: i64,
+    pub value: String,
+    pub proper_name: String,
+    pub description: Option<String>,
+    //The RGB HTML Color
+    pub color: Option<String>,
+    pub possible_titles: Option<StringList>,
+    pub priority: Option<i64>,
+    pub exclude: Option<StringList>,
+    pub position: Option<TitlePosition>,
+    pub word_boundary: Option<bool>,
+    pub leetspeak: Option<bool>,
+    //Where the title shows up in the list. Lowest first
+    pub ordering: i64,
+    //Retired titles are no longer given out but users keep them
+    pub retired: bool,
+    //Changed through the admin panel. Reloads leave it alone
+    pub edited: bool,
+    pub created: i64,
+    pub updated: i64,
+}
+
+impl DBTitle {
+    pub fn new(element: TitleElement, ordering: i64, time: i64) -> DBTitle {
+        DBTitle {
+            id: 0,
+            value: element.value.to_lowercase(),
+            proper_name: element.proper_name,
+            description: element.description,
+            color: element.color,
+            possible_titles: element.possible_titles.map(StringList),
+            priority: element.priority,
+            exclude: element.exclude.map(StringList),
+            position: element.position,
+            word_boundary: element.word_boundary,
+            leetspeak: element.leetspeak,
+            ordering,
+            retired: false,
+            edited: false,
+            created: time,
+            updated: time,
+        }
+    }
+}
+
+impl From<DBTitle> for TitleElement {
+    fn from(title: DBTitle) -> Self {
+        TitleElement {
+            description: title.description,
+            possible_titles: title.possible_titles.map(|x| x.0),
+            proper_name: title.proper_name,
+            value: title.value,
+            color: title.color,
+            priority: title.priority,
+            exclude: title.exclude.map(|x| x.0),
+            position: title.position,
+            word_boundary: title.word_boundary,
+            leetspeak: title.leetspeak,
+        }
+    }
+}
+
+impl ToSql<Text, Mysql> for StringList {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Mysql>) -> serialize::Result {
+        let s = serde_json::to_string(&self.0)?;
+        <String as ToSql<Text, Mysql>>::to_sql(&s, out)
+    }
+}
+
+impl FromSql<Text, Mysql> for StringList {
+    fn from_sql(
+        bytes: Option<&<diesel::mysql::Mysql as Backend>::RawValue>,
+    ) -> deserialize::Result<StringList> {
+        let t = <String as FromSql<Text, Mysql>>::from_sql(bytes)?;
+        let result = serde_json::from_str(&t)?;
+        Ok(StringList(result))
+    }
+}
+
+impl ToSql<Text, Mysql> for TitlePosition {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Mysql>) -> serialize::Result {
+        let s = self.to_string();
+        <String as ToSql<Text, Mysql>>::to_sql(&s, out)
+    }
+}
+
+impl FromSql<Text, Mysql> for TitlePosition {
+    fn from_sql(
+        bytes: Option<&<diesel::mysql::Mysql as Backend>::RawValue>,
+    ) -> deserialize::Result<TitlePosition> {
+        let t = <String as FromSql<Text, Mysql>>::from_sql(bytes)?;
+        let result: Result<TitlePosition, strum::ParseError> = TitlePosition::from_str(t.as_str());
+        if let Err(error) = result {
+            error!("Unable to Parse Title Position {} Value {}", error, t);
+            return Err(Box::new(error));
+        }
+        Ok(result.unwrap())
+    }
+}
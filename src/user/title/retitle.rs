@@ -0,0 +1,70 @@
+use diesel::MysqlConnection;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::error::internal_error::InternalError;
+use crate::user::action::{get_user_titles, update_matched_title};
+use crate::user::title::matcher::{best_title, NO_TITLE};
+use crate::user::title::Titles;
+
+/// A user whose title would be different with the current titles
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TitleChange {
+    pub id: i64,
+    pub username: String,
+    pub old: String,
+    pub new: String,
+    //Titles set by hand are reported but never changed
+    pub manual: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RetitleReport {
+    //If the changes were written to the database
+    pub applied: bool,
+    pub scanned: usize,
+    //Number of users that got a new title
+    pub updated: usize,
+    //Number of users that would get a new title but were set by hand
+    pub skipped_manual: usize,
+    pub changes: Vec<TitleChange>,
+}
+
+/// Runs the matcher over every user. The changes are only written if apply is true
+pub fn retitle_users(
+    titles: &Titles,
+    apply: bool,
+    conn: &MysqlConnection,
+) -> Result<RetitleReport, InternalError> {
+    let users = get_user_titles(conn)?;
+    let mut report = RetitleReport {
+        applied: apply,
+        scanned: users.len(),
+        ..RetitleReport::default()
+    };
+    for (id, username, old, manual) in users {
+        let new = best_title(&username, titles).unwrap_or_else(|| NO_TITLE.to_string());
+        if new.eq(&old) {
+            continue;
+        }
+        if manual {
+            report.skipped_manual += 1;
+        } else if apply {
+            report.updated += update_matched_title(&id, &new, conn)?;
+        }
+        report.changes.push(TitleChange {
+            id,
+            username,
+            old,
+            new,
+            manual,
+        });
+    }
+    if apply {
+        info!(
+            "Retitled {} of {} users. {} set by hand",
+            report.updated, report.scanned, report.skipped_manual
+        );
+    }
+    Ok(report)
+}
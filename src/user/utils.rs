@@ -1,33 +1,58 @@
-use actix_web::http::header::HeaderMap;
+use actix_web::http::header::{HeaderMap, USER_AGENT};
+use actix_web::HttpRequest;
 use chrono::Duration;
 use diesel::MysqlConnection;
-use log::info;
+use log::{info, warn};
 use rand::distributions::Alphanumeric;
 use rand::Rng;
+use std::net::IpAddr;
 use std::ops::Add;
+use std::str::FromStr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::error::internal_error::InternalError;
+use crate::settings::utils::get_setting_value;
 use crate::user::action;
 use crate::user::action::{
-    add_new_auth_token, add_opt, get_user_by_name, get_user_from_auth_token,
+    add_new_auth_token, add_opt, add_otp_attempt, count_otp_attempts_by_ip,
+    count_otp_attempts_by_user, delete_auth_token, delete_expired_auth_tokens,
+    get_auth_tokens_by_selector, get_legacy_auth_tokens, get_user_by_id, get_user_by_name,
+    renew_auth_token, update_auth_token_hash,
 };
-use crate::user::models::{AuthToken, Status, User, UserPermissions, UserProperties, OTP};
-use crate::utils::{get_current_time, is_valid};
+use crate::user::models::{AuthToken, OTPAttempt, Status, User, UserProperties, OTP};
+use crate::user::role::action::{add_default_roles, add_user_role, get_role_by_name};
+use crate::user::role::models::UserRole;
+use crate::user::title::matcher::{best_title, NO_TITLE};
+use crate::utils::get_current_time;
 use crate::Titles;
 use argon2::password_hash::rand_core::OsRng;
 use argon2::password_hash::SaltString;
 use argon2::{Argon2, PasswordHasher};
-pub fn get_user_by_header(
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The start of a token. Stored as is to find the token
+const SELECTOR_LENGTH: usize = 12;
+/// The rest of a token. Only stored hashed
+const VERIFIER_LENGTH: usize = 32;
+/// How often the expiration of a token that is being used is pushed back
+const TOKEN_RENEW_INTERVAL: i64 = 60 * 1000;
+
+/// The user and the token behind the bearer token. Expired tokens are ignored and tokens being used are renewed
+pub fn get_session_by_header(
     header_map: &HeaderMap,
     conn: &MysqlConnection,
-) -> Result<Option<User>, InternalError> {
+) -> Result<Option<(User, AuthToken)>, InternalError> {
     let option = header_map.get("Authorization");
     if option.is_none() {
         return Ok(None);
     }
     let x = option.unwrap().to_str();
-    if x.is_err() {}
+    if x.is_err() {
+        return Ok(None);
+    }
     let header = x.unwrap().to_string();
 
     let split = header.split(' ').collect::<Vec<&str>>();
@@ -41,11 +66,54 @@ pub fn get_user_by_header(
     }
     let value = value.unwrap().to_string();
     let key = option.unwrap().to_string();
-    if key.eq("Bearer") {
-        let result = get_user_from_auth_token(value, conn)?;
-        return Ok(result);
+    if !key.eq("Bearer") {
+        return Ok(None);
+    }
+    if !value.is_ascii() || value.len() <= SELECTOR_LENGTH {
+        return Ok(None);
+    }
+    let (selector, verifier) = value.split_at(SELECTOR_LENGTH);
+    let mut found = None;
+    for token in get_auth_tokens_by_selector(selector, conn)? {
+        if verify_keyed_hash(verifier, &token.token)? {
+            found = Some(token);
+            break;
+        }
     }
-    Ok(None)
+    if found.is_none() {
+        return Ok(None);
+    }
+    let mut token = found.unwrap();
+    let now = get_current_time();
+    if token.expiration <= now {
+        return Ok(None);
+    }
+    if now - token.last_used >= TOKEN_RENEW_INTERVAL {
+        token.expiration = token_expiration(token.created, now, conn)?;
+        token.last_used = now;
+        renew_auth_token(&token.id, token.expiration, now, conn)?;
+    }
+    let user = get_user_by_id(&token.user, conn)?;
+    Ok(user.map(|user| (user, token)))
+}
+
+/// When a token used now expires. The lifetime slides with each use up to the maximum lifetime
+pub fn token_expiration(
+    created: i64,
+    now: i64,
+    conn: &MysqlConnection,
+) -> Result<i64, InternalError> {
+    let lifetime: i64 = get_setting_value("security.token.lifetime", conn)?;
+    let max_lifetime: i64 = get_setting_value("security.token.max_lifetime", conn)?;
+    Ok((now + lifetime * 1000).min(created + max_lifetime * 1000))
+}
+
+/// The User-Agent header cut down to fit the database
+pub fn user_agent(header_map: &HeaderMap) -> Option<String> {
+    header_map
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.chars().take(255).collect())
 }
 
 pub fn otp_expiration() -> i64 {
@@ -56,23 +124,171 @@ pub fn otp_expiration() -> i64 {
         .as_millis() as i64
 }
 
+/// Creates a one time password for the user. Only its hash is stored
 pub fn generate_otp(user: &i64, conn: &MysqlConnection) -> Result<String, InternalError> {
-    let value = loop {
-        let opt = generate_otp_value();
-        if !crate::user::action::opt_exist(&opt, conn)? {
-            break opt;
-        }
-    };
+    let value = random_string(6);
     let opt = OTP {
         id: 0,
         user: *user,
-        password: value,
+        password: keyed_hash(&otp_value(user, &value))?,
         expiration: otp_expiration(),
         created: get_current_time(),
     };
     add_opt(&opt, conn)?;
-    Ok(opt.password)
+    Ok(value)
+}
+
+/// If the user or IP failed too many one time passwords within the lockout.
+/// A user of 0 only checks the IP
+pub fn otp_locked_out(user: &i64, ip: &str, conn: &MysqlConnection) -> Result<bool, InternalError> {
+    let lockout: i64 = get_setting_value("security.otp.lockout", conn)?;
+    let since = get_current_time() - lockout * 1000;
+    let max_ip: i64 = get_setting_value("security.otp.max_attempts_ip", conn)?;
+    if count_otp_attempts_by_ip(ip, since, conn)? >= max_ip {
+        return Ok(true);
+    }
+    if *user == 0 {
+        return Ok(false);
+    }
+    let max: i64 = get_setting_value("security.otp.max_attempts", conn)?;
+    Ok(count_otp_attempts_by_user(user, since, conn)? >= max)
 }
+
+pub fn add_otp_failure(user: &i64, ip: &str, conn: &MysqlConnection) -> Result<(), InternalError> {
+    let attempt = OTPAttempt {
+        id: 0,
+        user: *user,
+        ip: ip.to_string(),
+        created: get_current_time(),
+    };
+    add_otp_attempt(&attempt, conn)?;
+    Ok(())
+}
+
+/// Gives an imported user their roles back. Backups made before roles get the default roles
+pub fn restore_roles(
+    username: &str,
+    roles: Option<Vec<String>>,
+    conn: &MysqlConnection,
+) -> Result<(), InternalError> {
+    let user = get_user_by_name(username, conn)?;
+    if user.is_none() {
+        return Ok(());
+    }
+    let user = user.unwrap();
+    if roles.is_none() {
+        add_default_roles(&user.id, conn)?;
+        return Ok(());
+    }
+    for name in roles.unwrap() {
+        match get_role_by_name(&name, conn)? {
+            Some(role) => {
+                let value = UserRole {
+                    id: 0,
+                    user: user.id,
+                    role: role.id,
+                    created: get_current_time(),
+                };
+                add_user_role(&value, conn)?;
+            }
+            None => warn!("Unable to find the Role {} for {}", name, username),
+        }
+    }
+    Ok(())
+}
+
+/// The IP of the client. X-Forwarded-For is only used if the request came from one of the
+/// proxies in TRUSTED_PROXIES. Otherwise anyone could pick their own IP
+pub fn client_ip(request: &HttpRequest) -> String {
+    let trusted: Vec<IpAddr> = std::env::var("TRUSTED_PROXIES")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|value| IpAddr::from_str(value.trim()).ok())
+        .collect();
+    let forwarded_for = request
+        .headers()
+        .get("X-Forwarded-For")
+        .and_then(|value| value.to_str().ok());
+    resolve_ip(
+        request.peer_addr().map(|address| address.ip()),
+        forwarded_for,
+        &trusted,
+    )
+}
+
+/// Proxies add to the end of X-Forwarded-For so the last address that is not a trusted proxy
+/// is the client. Everything before it could have been sent by the client
+fn resolve_ip(peer: Option<IpAddr>, forwarded_for: Option<&str>, trusted: &[IpAddr]) -> String {
+    let mut ip = match peer {
+        Some(peer) => peer,
+        None => return "unknown".to_string(),
+    };
+    if trusted.contains(&ip) {
+        for value in forwarded_for.unwrap_or_default().rsplit(',') {
+            match IpAddr::from_str(value.trim()) {
+                Ok(forwarded) => {
+                    ip = forwarded;
+                    if !trusted.contains(&forwarded) {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    }
+    ip.to_string().chars().take(64).collect()
+}
+
+#[test]
+fn resolve_ip_test() {
+    let proxy = IpAddr::from_str("10.0.0.1").unwrap();
+    let client = IpAddr::from_str("203.0.113.5").unwrap();
+    assert_eq!(resolve_ip(Some(client), Some("1.2.3.4"), &[proxy]), "203.0.113.5");
+    assert_eq!(resolve_ip(Some(proxy), None, &[proxy]), "10.0.0.1");
+    assert_eq!(
+        resolve_ip(Some(proxy), Some("1.2.3.4, 203.0.113.5"), &[proxy]),
+        "203.0.113.5"
+    );
+    assert_eq!(
+        resolve_ip(Some(proxy), Some("203.0.113.5, 10.0.0.1"), &[proxy]),
+        "203.0.113.5"
+    );
+    assert_eq!(resolve_ip(Some(proxy), Some("junk"), &[proxy]), "10.0.0.1");
+    assert_eq!(resolve_ip(None, Some("1.2.3.4"), &[proxy]), "unknown");
+}
+
+/// If the value is the one time password. The user is part of the hash so it only works for them
+pub fn verify_otp(otp: &OTP, value: &str) -> Result<bool, InternalError> {
+    verify_keyed_hash(&otp_value(&otp.user, value), &otp.password)
+}
+
+fn otp_value(user: &i64, value: &str) -> String {
+    format!("{}:{}", user, value)
+}
+
+fn keyed_mac(value: &str) -> Result<HmacSha256, InternalError> {
+    let secret = std::env::var("TOKEN_SECRET")
+        .map_err(|_| InternalError::Error("Missing TOKEN_SECRET".to_string()))?;
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .map_err(|error| InternalError::Error(error.to_string()))?;
+    mac.update(value.as_bytes());
+    Ok(mac)
+}
+
+/// Hashes a token or one time password with the TOKEN_SECRET. A copy of the database is not enough to use them
+pub fn keyed_hash(value: &str) -> Result<String, InternalError> {
+    Ok(base64::encode(keyed_mac(value)?.finalize().into_bytes()))
+}
+
+/// Checks the value against a keyed hash in constant time
+pub fn verify_keyed_hash(value: &str, hash: &str) -> Result<bool, InternalError> {
+    let expected = match base64::decode(hash) {
+        Ok(expected) => expected,
+        Err(_) => return Ok(false),
+    };
+    Ok(keyed_mac(value)?.verify_slice(&expected).is_ok())
+}
+
 pub fn hash(password: String) -> Result<String, InternalError> {
     let salt = SaltString::generate(&mut OsRng);
 
@@ -83,37 +299,60 @@ pub fn hash(password: String) -> Result<String, InternalError> {
     Ok(password_hash)
 }
 
-fn generate_otp_value() -> String {
+pub fn random_string(length: usize) -> String {
     rand::thread_rng()
         .sample_iter(&Alphanumeric)
-        .take(6)
+        .take(length)
         .map(char::from)
         .collect()
 }
 
-pub fn create_token(user: &User, connection: &MysqlConnection) -> Result<AuthToken, InternalError> {
-    let s: String = rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(25)
-        .map(char::from)
-        .collect();
-    let token = AuthToken {
+pub fn create_token(
+    user: &User,
+    user_agent: Option<String>,
+    connection: &MysqlConnection,
+) -> Result<AuthToken, InternalError> {
+    let selector = random_string(SELECTOR_LENGTH);
+    let verifier = random_string(VERIFIER_LENGTH);
+    delete_expired_auth_tokens(connection)?;
+    let now = get_current_time();
+    let mut token = AuthToken {
         id: 0,
         user: user.id,
-        token: s,
-        created: get_current_time(),
+        selector: selector.clone(),
+        token: keyed_hash(&verifier)?,
+        created: now,
+        expiration: token_expiration(now, now, connection)?,
+        last_used: now,
+        user_agent,
     };
-    let _result = add_new_auth_token(&token, connection);
-
+    add_new_auth_token(&token, connection)?;
+    // Only the hash is stored. The user gets the token they have to send back
+    token.token = format!("{}{}", selector, verifier);
     Ok(token)
 }
 
+/// Hashes the tokens made before they were stored hashed. Their first characters become the selector
+pub fn hash_legacy_tokens(conn: &MysqlConnection) -> Result<usize, InternalError> {
+    let tokens = get_legacy_auth_tokens(conn)?;
+    for token in &tokens {
+        if !token.token.is_ascii() || token.token.len() <= SELECTOR_LENGTH {
+            delete_auth_token(&token.id, &token.user, conn)?;
+            continue;
+        }
+        let (selector, verifier) = token.token.split_at(SELECTOR_LENGTH);
+        update_auth_token_hash(&token.id, selector, &keyed_hash(verifier)?, conn)?;
+    }
+    Ok(tokens.len())
+}
+
+/// Adds the user if they do not exist yet. Returns true if the user was added
 pub fn quick_add(
     username: &str,
     discoverer: &str,
     conn: &MysqlConnection,
     titles: &Titles,
-) -> Result<(), InternalError> {
+) -> Result<bool, InternalError> {
     info!("Adding user {}", &username);
 
     let mut status = Status::Found;
@@ -143,18 +382,16 @@ pub fn quick_add(
             created: get_current_time(),
             discoverer: discoverer.to_string(),
             properties,
-            title: is_valid(&username, titles).unwrap_or_else(|| "No Title Identified".to_string()),
-            permissions: UserPermissions {
-                admin: false,
-                moderator: false,
-                submit: true,
-                review_user: false,
-                login: true,
-            },
+            title: best_title(&username, titles).unwrap_or_else(|| NO_TITLE.to_string()),
             password_changed: get_current_time(),
-            birthday: None
+            birthday: None,
+            title_manual: false,
         };
         action::add_new_user(&user, conn)?;
+        if let Some(user) = get_user_by_name(&username, conn)? {
+            add_default_roles(&user.id, conn)?;
+        }
+        return Ok(true);
     }
-    Ok(())
+    Ok(false)
 }
@@ -7,15 +7,13 @@ use std::str::FromStr;
 
 use crate::error::internal_error::InternalError;
 use crate::settings::action::get_setting;
-use crate::{Titles, User};
+use crate::User;
 use rust_embed::RustEmbed;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::user::models::UserProperties;
+use crate::reddit::RedditGateway;
 use log::error;
-use rraw::auth::{AnonymousAuthenticator, PasswordAuthenticator};
-use rraw::Client;
-use rraw::utils::options::FriendType;
 
 #[derive(RustEmbed)]
 #[folder = "$CARGO_MANIFEST_DIR/resources"]
@@ -56,36 +54,30 @@ pub(crate) fn get_current_time() -> i64 {
         .as_millis() as i64
 }
 
-pub async fn send_login(user: &str, password: String, rr: &Client<PasswordAuthenticator>) -> Result<(), InternalError> {
+pub async fn send_login(user: &str, password: String, rr: &dyn RedditGateway) -> Result<(), InternalError> {
     let string = build_message(user, password)?;
-    rr.subreddit("RedditNobility").await?
-        .compose(
-            user.to_string(),
-            "RedditNobility Login".to_string(),
-            string,
-        )
-        .await?;
+    rr.compose(user, "RedditNobility Login", &string).await?;
     Ok(())
 }
 
 fn build_message(user: &str, password: String) -> Result<String, InternalError> {
+    let site =
+        std::env::var("SITE_URL").unwrap_or_else(|_| "https://redditnobility.org".to_string());
     let string = Resources::file_get_string("login-message");
     let string = string
+        .replace("{{URL}}", &format!("{}/api/login/reddit", site.trim_end_matches('/')))
         .replace("{{PASSWORD}}", &password)
         .replace("{{USERNAME}}", user);
     Ok(string)
 }
 
-pub async fn approve_user(user: &User, client: &Client<PasswordAuthenticator>) -> bool {
-    let subreddit = client
-        .subreddit("RedditNobility").await.unwrap();
-    let result = subreddit.add_friend(user.username.clone(), FriendType::Contributor)
-        .await;
+pub async fn approve_user(user: &User, client: &dyn RedditGateway) -> bool {
+    let result = client.add_contributor(&user.username).await;
     if let Err(error) = result {
-        error!("Unable to approve User {}", error);
+        error!("Unable to approve User {:?}", error);
         false
-    } else if let Ok(friend) = result {
-        friend.success
+    } else if let Ok(success) = result {
+        success
     }else{
         false
     }
@@ -93,23 +85,6 @@ pub async fn approve_user(user: &User, client: &Client<PasswordAuthenticator>) -
 
 pub fn yeet<T>(_drop: T) {}
 
-pub fn is_valid(username: &str, titles: &Titles) -> Option<String> {
-    let username = username.to_lowercase();
-    for title in &titles.titles {
-        if username.contains(&title.value) {
-            if let Some(possibles) = &title.possible_titles {
-                for possible in possibles {
-                    if username.contains(possible) {
-                        return Some(possible.clone());
-                    }
-                }
-            }
-            return Some(title.value.clone());
-        }
-    }
-    None
-}
-
 #[tokio::test]
 async fn valid_test() {
     use hyper::{Body, Client, Method, Request};
@@ -125,35 +100,81 @@ async fn valid_test() {
     let response = client.request(request).await.unwrap();
     let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
     let string = String::from_utf8(bytes.to_vec()).unwrap();
-    let titles: Titles = serde_json::from_str(string.as_str()).unwrap();
-    let _option = is_valid(&"KingTuxWH".to_string(), &titles);
-    assert_eq!(is_valid(&"KingTuxWH".to_string(), &titles).unwrap(), "king");
-    assert_eq!(is_valid(&"QueenTux".to_string(), &titles).unwrap(), "queen");
-    assert_eq!(
-        is_valid(&"VikingTux".to_string(), &titles).unwrap(),
-        "viking"
-    );
-    assert_eq!(is_valid(&"LordTux".to_string(), &titles).unwrap(), "lord");
-    assert_eq!(is_valid(&"CzArTux".to_string(), &titles).unwrap(), "czar");
+    let titles: crate::Titles = serde_json::from_str(string.as_str()).unwrap();
+    use crate::user::title::matcher::best_title;
+
+    assert_eq!(best_title("KingTuxWH", &titles).unwrap(), "king");
+    assert_eq!(best_title("QueenTux", &titles).unwrap(), "queen");
+    assert_eq!(best_title("VikingTux", &titles).unwrap(), "viking");
+    assert_eq!(best_title("LordTux", &titles).unwrap(), "lord");
+    assert_eq!(best_title("CzArTux", &titles).unwrap(), "czar");
+}
+
+#[tokio::test]
+async fn send_login_test() {
+    let reddit = crate::reddit::fake::FakeReddit::default();
+    reddit.add_account("KingTuxWH");
+    send_login("KingTuxWH", "Tux123".to_string(), &reddit).await.unwrap();
+    let messages = reddit.messages();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].username, "KingTuxWH");
+    assert!(messages[0].body.contains("Tux123"));
+    assert!(messages[0].body.contains("/api/login/reddit"));
+
+    // Reddit does not know the account so the password never goes out
+    assert!(send_login("QueenTux", "Tux123".to_string(), &reddit).await.is_err());
+    assert_eq!(reddit.messages().len(), 1);
+}
+
+#[tokio::test]
+async fn get_avatar_test() {
+    let reddit = crate::reddit::fake::FakeReddit::default();
+    reddit.add_account("KingTuxWH");
+    let properties = UserProperties {
+        avatar: None,
+        description: None,
+    };
+    let avatar = get_avatar("KingTuxWH", &properties, &reddit).await.unwrap();
+    assert_eq!(avatar, "https://example.com/KingTuxWH.png");
+    assert_eq!(get_avatar("QueenTux", &properties, &reddit).await.unwrap(), "");
+
+    let properties = UserProperties {
+        avatar: Some("https://example.com/set.png".to_string()),
+        description: None,
+    };
+    let avatar = get_avatar("QueenTux", &properties, &reddit).await.unwrap();
+    assert_eq!(avatar, "https://example.com/set.png");
+}
+
+#[tokio::test]
+async fn approve_user_test() {
+    use crate::reddit::fake::found_user;
+
+    let reddit = crate::reddit::fake::FakeReddit::default();
+    reddit.add_account("KingTuxWH");
+    let user = found_user("KingTuxWH");
+    assert!(approve_user(&user, &reddit).await);
+    assert!(reddit.is_contributor("KingTuxWH"));
+
+    let missing = found_user("QueenTux");
+    assert!(!approve_user(&missing, &reddit).await);
 }
 
-pub async fn get_avatar(username: &str, user: &UserProperties) -> Result<String, InternalError> {
+pub async fn get_avatar(username: &str, user: &UserProperties, client: &dyn RedditGateway) -> Result<String, InternalError> {
     let option1 = user.avatar.as_ref();
     if option1.is_some() && !option1.unwrap().is_empty() {
         return Ok(option1.unwrap().clone());
     }
 
-    let client = rraw::Client::login(
-        AnonymousAuthenticator::new(),
-        "Robotic Monarch by u/KingTuxWH".to_string(),
-    )
-        .await?;
     let reddit_user = client.user(username).await?;
+    if reddit_user.is_none() {
+        return Ok("".to_string());
+    }
+    let reddit_user = reddit_user.unwrap();
 
-    let avatar = reddit_user.user.snoovatar_img;
-    if !avatar.is_empty() {
-        return Ok(avatar);
+    if !reddit_user.snoovatar_img.is_empty() {
+        return Ok(reddit_user.snoovatar_img);
     }
 
-    Ok(reddit_user.user.icon_img)
+    Ok(reddit_user.icon_img)
 }
@@ -0,0 +1,29 @@
+package io.quicktype;
+
+import java.io.IOException;
+import com.fasterxml.jackson.annotation.*;
+
+/**
+ * Where in the username the title has to be
+ */
+public enum Position {
+    ANYWHERE, PREFIX, SUFFIX;
+
+    @JsonValue
+    public String toValue() {
+        switch (this) {
+            case ANYWHERE: return "anywhere";
+            case PREFIX: return "prefix";
+            case SUFFIX: return "suffix";
+        }
+        return null;
+    }
+
+    @JsonCreator
+    public static Position forValue(String value) throws IOException {
+        if (value.equals("anywhere")) return ANYWHERE;
+        if (value.equals("prefix")) return PREFIX;
+        if (value.equals("suffix")) return SUFFIX;
+        throw new IOException("Cannot deserialize Position");
+    }
+}
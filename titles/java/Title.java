@@ -3,8 +3,20 @@ package io.quicktype;
 import com.fasterxml.jackson.annotation.*;
 
 public class Title {
+    private String id;
+    private String schema;
     private TitleElement[] titles;
 
+    @JsonProperty("$id")
+    public String getID() { return id; }
+    @JsonProperty("$id")
+    public void setID(String value) { this.id = value; }
+
+    @JsonProperty("$schema")
+    public String getSchema() { return schema; }
+    @JsonProperty("$schema")
+    public void setSchema(String value) { this.schema = value; }
+
     @JsonProperty("titles")
     public TitleElement[] getTitles() { return titles; }
     @JsonProperty("titles")
@@ -5,9 +5,14 @@ import com.fasterxml.jackson.annotation.*;
 public class TitleElement {
     private String color;
     private String description;
+    private String[] exclude;
+    private Boolean leetspeak;
+    private Position position;
     private String[] possibleTitles;
+    private Long priority;
     private String properName;
     private String value;
+    private Boolean wordBoundary;
 
     /**
      * The RGB HTML Color
@@ -22,11 +27,43 @@ public class TitleElement {
     @JsonProperty("description")
     public void setDescription(String value) { this.description = value; }
 
+    /**
+     * Usernames containing any of these are never given the title
+     */
+    @JsonProperty("exclude")
+    public String[] getExclude() { return exclude; }
+    @JsonProperty("exclude")
+    public void setExclude(String[] value) { this.exclude = value; }
+
+    /**
+     * Also match the title written in leetspeak
+     */
+    @JsonProperty("leetspeak")
+    public Boolean getLeetspeak() { return leetspeak; }
+    @JsonProperty("leetspeak")
+    public void setLeetspeak(Boolean value) { this.leetspeak = value; }
+
+    /**
+     * Where in the username the title has to be
+     */
+    @JsonProperty("position")
+    public Position getPosition() { return position; }
+    @JsonProperty("position")
+    public void setPosition(Position value) { this.position = value; }
+
     @JsonProperty("possibleTitles")
     public String[] getPossibleTitles() { return possibleTitles; }
     @JsonProperty("possibleTitles")
     public void setPossibleTitles(String[] value) { this.possibleTitles = value; }
 
+    /**
+     * Titles with a higher priority win over titles with a better match
+     */
+    @JsonProperty("priority")
+    public Long getPriority() { return priority; }
+    @JsonProperty("priority")
+    public void setPriority(Long value) { this.priority = value; }
+
     @JsonProperty("properName")
     public String getProperName() { return properName; }
     @JsonProperty("properName")
@@ -36,4 +73,12 @@ public class TitleElement {
     public String getValue() { return value; }
     @JsonProperty("value")
     public void setValue(String value) { this.value = value; }
+
+    /**
+     * Only match the title if it is its own word inside the username
+     */
+    @JsonProperty("wordBoundary")
+    public Boolean getWordBoundary() { return wordBoundary; }
+    @JsonProperty("wordBoundary")
+    public void setWordBoundary(Boolean value) { this.wordBoundary = value; }
 }
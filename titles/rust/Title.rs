@@ -15,6 +15,12 @@ extern crate serde_derive;
 
 #[derive(Serialize, Deserialize)]
 pub struct Title {
+    #[serde(rename = "$id")]
+    id: Option<String>,
+
+    #[serde(rename = "$schema")]
+    schema: Option<String>,
+
     #[serde(rename = "titles")]
     titles: Vec<TitleElement>,
 }
@@ -28,12 +34,45 @@ pub struct TitleElement {
     #[serde(rename = "description")]
     description: Option<String>,
 
+    /// Usernames containing any of these are never given the title
+    #[serde(rename = "exclude")]
+    exclude: Option<Vec<String>>,
+
+    /// Also match the title written in leetspeak
+    #[serde(rename = "leetspeak")]
+    leetspeak: Option<bool>,
+
+    /// Where in the username the title has to be
+    #[serde(rename = "position")]
+    position: Option<Position>,
+
     #[serde(rename = "possibleTitles")]
     possible_titles: Option<Vec<String>>,
 
+    /// Titles with a higher priority win over titles with a better match
+    #[serde(rename = "priority")]
+    priority: Option<i64>,
+
     #[serde(rename = "properName")]
     proper_name: String,
 
     #[serde(rename = "value")]
     value: String,
+
+    /// Only match the title if it is its own word inside the username
+    #[serde(rename = "wordBoundary")]
+    word_boundary: Option<bool>,
+}
+
+/// Where in the username the title has to be
+#[derive(Serialize, Deserialize)]
+pub enum Position {
+    #[serde(rename = "anywhere")]
+    Anywhere,
+
+    #[serde(rename = "prefix")]
+    Prefix,
+
+    #[serde(rename = "suffix")]
+    Suffix,
 }
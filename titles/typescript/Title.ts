@@ -8,18 +8,49 @@
 // match the expected interface, even if the JSON is valid.
 
 export interface Title {
-    titles: TitleElement[];
+    $id?:     string;
+    $schema?: string;
+    titles:   TitleElement[];
 }
 
 export interface TitleElement {
     /**
      * The RGB HTML Color
      */
-    color?:          string;
-    description?:    string;
+    color?:       string;
+    description?: string;
+    /**
+     * Usernames containing any of these are never given the title
+     */
+    exclude?: string[];
+    /**
+     * Also match the title written in leetspeak
+     */
+    leetspeak?: boolean;
+    /**
+     * Where in the username the title has to be
+     */
+    position?:       Position;
     possibleTitles?: string[];
-    properName:      string;
-    value:           string;
+    /**
+     * Titles with a higher priority win over titles with a better match
+     */
+    priority?:   number;
+    properName:  string;
+    value:       string;
+    /**
+     * Only match the title if it is its own word inside the username
+     */
+    wordBoundary?: boolean;
+}
+
+/**
+ * Where in the username the title has to be
+ */
+export enum Position {
+    Anywhere = "anywhere",
+    Prefix = "prefix",
+    Suffix = "suffix",
 }
 
 // Converts JSON strings to/from your types
@@ -168,13 +199,25 @@ function r(name: string) {
 
 const typeMap: any = {
     "Title": o([
+        { json: "$id", js: "$id", typ: u(undefined, "") },
+        { json: "$schema", js: "$schema", typ: u(undefined, "") },
         { json: "titles", js: "titles", typ: a(r("TitleElement")) },
     ], false),
     "TitleElement": o([
         { json: "color", js: "color", typ: u(undefined, "") },
         { json: "description", js: "description", typ: u(undefined, "") },
+        { json: "exclude", js: "exclude", typ: u(undefined, a("")) },
+        { json: "leetspeak", js: "leetspeak", typ: u(undefined, true) },
+        { json: "position", js: "position", typ: u(undefined, r("Position")) },
         { json: "possibleTitles", js: "possibleTitles", typ: u(undefined, a("")) },
+        { json: "priority", js: "priority", typ: u(undefined, 0) },
         { json: "properName", js: "properName", typ: "" },
         { json: "value", js: "value", typ: "" },
+        { json: "wordBoundary", js: "wordBoundary", typ: u(undefined, true) },
     ], false),
+    "Position": [
+        "anywhere",
+        "prefix",
+        "suffix",
+    ],
 };